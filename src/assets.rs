@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+use crate::{APP_ID, ASSET_SOUNDS};
+
+/// Environment variable overriding the directory the built-in sound files are read from
+pub const ASSETS_DIR_ENV: &str = "FRIENDS_NOTIFIER_ASSETS";
+
+// Built-in sound paths are stored relative to this folder, see ASSET_SOUNDS
+const ASSETS_PREFIX: &str = "assets/";
+
+static ASSETS_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Directory holding the built-in sound files, looked up once and None when the embedded copies are used
+/// The env var override comes first, then the executable directory, the XDG data directory and the working directory
+pub fn assets_dir() -> Option<&'static Path> {
+    ASSETS_DIR
+        .get_or_init(|| {
+            let dir = candidate_dirs().into_iter().find(|dir| has_sounds(dir));
+            match &dir {
+                Some(dir) => info!("Loading built-in sounds from {}", dir.display()),
+                None => warn!("Built-in sound files not found, using the embedded ones"),
+            }
+            dir
+        })
+        .as_deref()
+}
+
+/// Path to read a sound from, built-in sounds are resolved against the assets directory
+/// Other paths, such as imported sounds, are returned as they are
+pub fn resolve(path: &str) -> PathBuf {
+    locate(path, assets_dir())
+}
+
+fn locate(path: &str, dir: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix(ASSETS_PREFIX), dir) {
+        (Some(file), Some(dir)) => dir.join(file),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn candidate_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(dir) = std::env::var_os(ASSETS_DIR_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        dirs.push(dir.join(ASSETS_PREFIX));
+    }
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    if let Some(dir) = data_home {
+        dirs.push(dir.join(APP_ID).join(ASSETS_PREFIX));
+    }
+    // Previous behaviour, kept for running from the repository
+    if let Ok(dir) = std::env::current_dir() {
+        dirs.push(dir.join(ASSETS_PREFIX));
+    }
+    dirs
+}
+
+fn has_sounds(dir: &Path) -> bool {
+    ASSET_SOUNDS.iter().any(|(_, path)| locate(path, Some(dir)).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_built_in_sounds_are_resolved_against_the_assets_dir() {
+        let dir = Path::new("/opt/friends_notifier/assets");
+        assert_eq!(locate("assets/notification-1.mp3", Some(dir)), dir.join("notification-1.mp3"));
+        assert_eq!(locate("assets/notification-1.mp3", None), PathBuf::from("assets/notification-1.mp3"));
+        assert_eq!(locate("/home/user/sounds/ping.wav", Some(dir)), PathBuf::from("/home/user/sounds/ping.wav"));
+    }
+}
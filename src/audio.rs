@@ -0,0 +1,265 @@
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{Decoder, Device, OutputStream, OutputStreamBuilder, Sink};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Sound;
+use crate::engine::Message;
+
+/// File types that can be imported as custom sounds
+pub const SOUND_EXTENSIONS: [&str; 4] = ["wav", "ogg", "mp3", "flac"];
+
+// Devices are checked before each sound and otherwise once a minute,
+// to follow the default device and pick up a preferred one plugged back in
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// Copies of the built-in sounds, played when their file cannot be read
+const EMBEDDED_SOUNDS: [&[u8]; 13] = [
+    include_bytes!("../assets/notification-1.mp3"),
+    include_bytes!("../assets/notification-2.mp3"),
+    include_bytes!("../assets/notification-3.mp3"),
+    include_bytes!("../assets/notification-4.mp3"),
+    include_bytes!("../assets/notification-5.mp3"),
+    include_bytes!("../assets/notification-6.mp3"),
+    include_bytes!("../assets/notification-7.mp3"),
+    include_bytes!("../assets/notification-8.mp3"),
+    include_bytes!("../assets/notification-9.mp3"),
+    include_bytes!("../assets/notification-10.mp3"),
+    include_bytes!("../assets/notification-11.mp3"),
+    include_bytes!("../assets/notification-12.mp3"),
+    include_bytes!("../assets/notification-13.mp3"),
+];
+
+/// State of the audio thread, read by the front-ends
+#[derive(Debug, Default)]
+pub struct AudioState {
+    // Name of the device sounds are played on, None when there is no usable device
+    pub device: Mutex<Option<String>>,
+    // Available output devices, refreshed along with the device in use
+    pub devices: Mutex<Vec<String>>,
+}
+
+/// Thread responsible to initialize the audio stream, load sound files and play them on demand
+/// The stream is reopened when it fails or the device to use changes, the preferred device when available and the default one otherwise
+/// `on_change` is called when the device in use or the available devices change
+pub fn start_audio_message_receiver(s_rx: Receiver<Message>, state: Arc<AudioState>, on_change: impl Fn() + Send + 'static) {
+    thread::spawn(move || {
+        // Load built-in sound files in memory, custom ones are loaded the first time they are played
+        let mut sound_files = vec![];
+        for ((_, path), embedded) in crate::ASSET_SOUNDS.iter().zip(EMBEDDED_SOUNDS) {
+            let bytes = std::fs::read(crate::assets::resolve(path)).unwrap_or_else(|_| embedded.to_vec());
+            sound_files.push((path.to_string(), bytes));
+        }
+        // Set from the stream error callback, the stream is then reopened
+        let stream_failed = Arc::new(AtomicBool::new(false));
+        let mut output: Option<(OutputStream, String)> = None;
+        let mut preferred_device: Option<String> = None;
+        // Each sound plays on its own sink so the master volume also applies to the ones still playing
+        let mut master_volume = 1.0;
+        let mut playing: Vec<(Sink, f32)> = vec![];
+        let mut checked_at: Option<Instant> = None;
+        // Sound waiting for the devices to be checked, it is played right after
+        let mut deferred: Option<Message> = None;
+        loop {
+            let is_check_due = checked_at.is_none_or(|t| t.elapsed() >= DEVICE_CHECK_INTERVAL);
+            let is_checked = is_check_due || stream_failed.load(Ordering::Relaxed) || deferred.is_some();
+            if is_checked {
+                checked_at = Some(Instant::now());
+                check_devices(&mut output, preferred_device.as_deref(), &stream_failed, &mut playing, &state, &on_change);
+            }
+
+            // When the message fires we play the sound at the specific path
+            let timeout = checked_at.map_or(Duration::ZERO, |t| DEVICE_CHECK_INTERVAL.saturating_sub(t.elapsed()));
+            let msg = match deferred.take().map(Ok).unwrap_or_else(|| s_rx.recv_timeout(timeout)) {
+                Ok(msg) => msg,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            playing.retain(|(sink, _)| !sink.empty());
+            match msg {
+                // The default device might have changed since the last check, the sound is played on the one in use now
+                Message::PlaySound(..) if !is_checked => deferred = Some(msg),
+                Message::PlaySound(path, volume) => {
+                    if !sound_files.iter().any(|f| f.0 == path)
+                        && let Ok(bytes) = std::fs::read(&path)
+                    {
+                        sound_files.push((path.clone(), bytes));
+                    }
+                    if let Some((stream, _)) = &output
+                        && let Some((_, sound_bytes)) = sound_files.iter().find(|f| f.0 == path)
+                        && let Ok(source) = Decoder::new(Cursor::new(sound_bytes.clone()))
+                    {
+                        let volume = volume as f32 / 100.0;
+                        let sink = Sink::connect_new(stream.mixer());
+                        sink.set_volume(master_volume * volume);
+                        sink.append(source);
+                        playing.push((sink, volume));
+                    }
+                }
+                Message::SetOutputDevice(device) => {
+                    preferred_device = device;
+                    checked_at = None;
+                }
+                Message::SetVolume(v) => {
+                    master_volume = v as f32 / 100.0;
+                    for (sink, volume) in playing.iter() {
+                        sink.set_volume(master_volume * volume);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// Follows the device to use, the previous one might have been unplugged or replaced
+// The stream is reopened when it failed or the device changed, the state is updated with the device in use and the available ones
+fn check_devices(
+    output: &mut Option<(OutputStream, String)>,
+    preferred_device: Option<&str>,
+    stream_failed: &Arc<AtomicBool>,
+    playing: &mut Vec<(Sink, f32)>,
+    state: &AudioState,
+    on_change: &impl Fn(),
+) {
+    let device = select_device(preferred_device);
+    let device_name = device.as_ref().and_then(|d| d.name().ok());
+    if stream_failed.swap(false, Ordering::Relaxed) || output.as_ref().map(|(_, name)| name) != device_name.as_ref() {
+        playing.clear();
+        *output = device.and_then(|d| open_device(d, stream_failed));
+    }
+    let name = output.as_ref().map(|(_, name)| name.clone());
+    let devices = output_devices();
+    let is_device_changed = *state.device.lock().unwrap() != name;
+    let is_list_changed = *state.devices.lock().unwrap() != devices;
+    if is_device_changed {
+        match &name {
+            Some(name) => info!("Playing sounds on {name}"),
+            None => warn!("No audio device available"),
+        }
+    }
+    if is_device_changed || is_list_changed {
+        *state.device.lock().unwrap() = name;
+        *state.devices.lock().unwrap() = devices;
+        on_change();
+    }
+}
+
+// The preferred device while it is available, the default one otherwise
+fn select_device(preferred: Option<&str>) -> Option<Device> {
+    let host = rodio::cpal::default_host();
+    preferred
+        .and_then(|name| host.output_devices().ok()?.find(|d| d.name().is_ok_and(|n| n == name)))
+        .or_else(|| host.default_output_device())
+}
+
+fn output_devices() -> Vec<String> {
+    let host = rodio::cpal::default_host();
+    host.output_devices().map(|devices| devices.filter_map(|d| d.name().ok()).collect()).unwrap_or_default()
+}
+
+// Opens the output device along with its name, None when it cannot be used
+fn open_device(device: Device, stream_failed: &Arc<AtomicBool>) -> Option<(OutputStream, String)> {
+    let name = device.name().ok()?;
+    let failed = stream_failed.clone();
+    let mut stream = OutputStreamBuilder::from_device(device)
+        .ok()?
+        .with_error_callback(move |_| failed.store(true, Ordering::Relaxed))
+        .open_stream_or_fallback()
+        .ok()?;
+    stream.log_on_drop(false);
+    Some((stream, name))
+}
+
+/// Directory imported sounds are copied to, inside the directory of the persisted state
+pub fn sounds_dir(storage_dir: &Path) -> PathBuf {
+    storage_dir.join("sounds")
+}
+
+/// Whether the file is one of the copies in the managed sounds directory `dir`
+/// Only those are deleted along with their sound, or accepted from an imported configuration
+pub fn is_managed_sound(path: &str, dir: &Path) -> bool {
+    is_inside(Path::new(path), dir)
+}
+
+// Both paths are resolved first so '..' components or links cannot lead outside of the directory
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    match (path.canonicalize(), dir.canonicalize()) {
+        (Ok(path), Ok(dir)) => path.is_file() && path.parent() == Some(dir.as_path()),
+        _ => false,
+    }
+}
+
+/// Copies an audio file into the managed sounds directory, named after the file
+/// Returns None when the file type is not supported or it cannot be decoded
+pub fn import_sound(file: &Path, dir: &Path) -> Option<Sound> {
+    let extension = file.extension()?.to_str()?.to_lowercase();
+    if !SOUND_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+    let bytes = std::fs::read(file).ok()?;
+    Decoder::new(Cursor::new(bytes.clone())).ok()?;
+    // Copies get a unique name so importing files with the same name never overwrites a sound in use
+    std::fs::create_dir_all(dir).ok()?;
+    let path = dir.join(format!("{}.{extension}", Uuid::new_v4()));
+    std::fs::write(&path, bytes).ok()?;
+    Some(Sound {
+        label: file.file_stem()?.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_files_directly_in_the_directory_are_inside() {
+        let dir = std::env::temp_dir().join(format!("sounds-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("horn.ogg"), b"").unwrap();
+        std::fs::write(dir.join("nested/horn.ogg"), b"").unwrap();
+
+        assert!(is_inside(&dir.join("horn.ogg"), &dir));
+        assert!(is_inside(&dir.join("nested/../horn.ogg"), &dir));
+        assert!(!is_inside(&dir.join("nested/horn.ogg"), &dir));
+        assert!(!is_inside(&dir.join("missing.ogg"), &dir));
+        assert!(!is_inside(&dir.join("../horn.ogg"), &dir));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn imported_sounds_are_copied_under_unique_names() {
+        let root = std::env::temp_dir().join(format!("import-{}", Uuid::new_v4()));
+        let dir = sounds_dir(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let horn = root.join("Horn.mp3");
+        std::fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/notification-1.mp3"), &horn).unwrap();
+
+        let first = import_sound(&horn, &dir).unwrap();
+        assert_eq!(first.label, "Horn");
+        assert!(is_managed_sound(&first.path, &dir));
+        assert_eq!(std::fs::read(&first.path).unwrap(), std::fs::read(&horn).unwrap());
+        assert!(!is_managed_sound(&horn.to_string_lossy(), &dir));
+
+        // The same file imported twice keeps both copies
+        let second = import_sound(&horn, &dir).unwrap();
+        assert_ne!(first.path, second.path);
+        assert!(is_managed_sound(&first.path, &dir) && is_managed_sound(&second.path, &dir));
+
+        // Files that cannot be played are not copied
+        std::fs::write(root.join("broken.mp3"), b"not a sound").unwrap();
+        std::fs::copy(&horn, root.join("horn.txt")).unwrap();
+        assert_eq!(import_sound(&root.join("broken.mp3"), &dir), None);
+        assert_eq!(import_sound(&root.join("horn.txt"), &dir), None);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,498 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::debounce::{Hysteresis, StatusTracker};
+use crate::presence::{ApiFriend, FriendStatus, Presence};
+use crate::rules::{Rule, RuleAction};
+use crate::source::DEMO_FRIENDS;
+
+/// Everything the user configures, persisted on shutdown
+/// Field names match the previously persisted app state so existing configurations keep loading
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub struct Config {
+    // Layout version, see migrations::SCHEMA_VERSION
+    pub schema_version: u32,
+    pub friends: Vec<Friend>,
+    pub groups: Vec<FriendGroup>,
+    pub rules: Vec<Rule>,
+    pub native_notification: bool,
+    pub volume: u8,
+    // Sounds play on the default output device when None or when this one is unavailable
+    pub output_device: Option<String>,
+    // Notifications and rules stay silent, toggled from the tray
+    pub muted: bool,
+    // Sound files imported by the user, copied into the managed sounds directory
+    pub custom_sounds: Vec<Sound>,
+    pub notify_away_status: bool,
+    pub notify_in_game_status: bool,
+    // Debouncing of status changes, see Hysteresis
+    pub status_dwell_secs: u16,
+    pub notify_cooldown_secs: u16,
+    pub missed_polls: u8,
+    // Days the history entries are kept for, 0 keeps them forever
+    pub history_retention_days: u32,
+    pub dark_mode: bool,
+    // Name of the active profile, its friends, rules and flags are the ones above
+    pub profile: String,
+    // Inactive profiles, swapped with the fields above when switching
+    pub profiles: Vec<Profile>,
+}
+
+/// Named set of tracked friends, rules and notification flags, e.g. "Ranked grind" or "Weekend with friends"
+/// Groups, sounds and the other settings are shared by every profile
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)] // new fields get default values when deserializing old state
+pub struct Profile {
+    pub name: String,
+    pub friends: Vec<Friend>,
+    pub rules: Vec<Rule>,
+    pub native_notification: bool,
+    pub notify_away_status: bool,
+    pub notify_in_game_status: bool,
+    pub volume: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)] // new fields get default values when deserializing old state
+pub struct Friend {
+    pub uuid: Uuid,
+    pub name: String,
+    // Resolved from the friends list the first time the name matches, tracking then follows renames
+    pub puuid: Option<String>,
+    // Riot id before the last detected rename, shown as a note on the row
+    pub previous_name: Option<String>,
+    pub sound: Sound,
+    // Volume in percent of the notifications of this friend, scaled by the master volume
+    pub volume: u8,
+    pub group: Option<Uuid>,
+    pub is_repeat: bool,
+    pub notify_timer: u16,
+    // Sent once when the friend leaves a game, '{name}' in the message is replaced with the friend name
+    pub notify_game_end: bool,
+    pub game_end_sound: Sound,
+    pub game_end_volume: u8,
+    pub game_end_message: String,
+    #[serde(skip)]
+    pub enabled: bool,
+    // Runtime state, owned by the engine
+    #[serde(skip)]
+    pub status: FriendStatus,
+    #[serde(skip)]
+    pub presence: Presence,
+    #[serde(skip)]
+    pub tracker: StatusTracker,
+}
+
+// Named set of friends sharing a default sound, notifications are only sent while the group is enabled
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FriendGroup {
+    pub uuid: Uuid,
+    pub name: String,
+    pub sound: Sound,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Sound {
+    pub label: String,
+    pub path: String,
+}
+
+impl Config {
+    // Demo state tracks the friends replayed by the scripted source
+    pub fn demo() -> Self {
+        Self {
+            friends: DEMO_FRIENDS
+                .iter()
+                .map(|name| Friend {
+                    name: name.to_string(),
+                    enabled: true,
+                    ..Default::default()
+                })
+                .collect(),
+            notify_away_status: true,
+            // The script changes statuses on every poll
+            status_dwell_secs: 0,
+            ..Default::default()
+        }
+    }
+
+    // Tracks a friend picked from the friends list, empty rows of the same group are filled before new ones are added
+    pub fn add_friend(&mut self, api_friend: &ApiFriend, group: Option<Uuid>) {
+        let index = match self.friends.iter().position(|f| f.name.is_empty() && !f.enabled && f.group == group) {
+            Some(i) => i,
+            None => {
+                self.friends.push(self.new_friend(group));
+                self.friends.len() - 1
+            }
+        };
+        let friend = &mut self.friends[index];
+        friend.name = api_friend.riot_id.clone();
+        friend.puuid = Some(api_friend.puuid.clone());
+    }
+
+    // Friends added to a group start with the group default sound
+    pub fn new_friend(&self, group: Option<Uuid>) -> Friend {
+        let mut friend = Friend { group, ..Default::default() };
+        if let Some(g) = self.groups.iter().find(|g| Some(g.uuid) == group) {
+            friend.sound = g.sound.clone();
+        }
+        friend
+    }
+
+    // Creates a group for each client friend group, tracking all of its members
+    pub fn import_client_group(&mut self, group_name: &str, api_friends: &[ApiFriend]) {
+        let uuid = match self.groups.iter().find(|g| g.name == group_name) {
+            Some(g) => g.uuid,
+            None => {
+                let g = FriendGroup {
+                    name: group_name.to_string(),
+                    ..Default::default()
+                };
+                self.groups.push(g.clone());
+                g.uuid
+            }
+        };
+        for api_friend in api_friends.iter().filter(|f| f.group_name == group_name) {
+            if !self.friends.iter().any(|f| f.matches(api_friend)) {
+                self.add_friend(api_friend, Some(uuid));
+            }
+        }
+    }
+
+    pub fn is_group_enabled(&self, group: Option<Uuid>) -> bool {
+        self.groups.iter().find(|g| Some(g.uuid) == group).is_none_or(|g| g.enabled)
+    }
+
+    // Built-in sounds come first, followed by the imported ones
+    pub fn sounds(&self) -> Vec<Sound> {
+        Sound::builtins().chain(self.custom_sounds.iter().cloned()).collect()
+    }
+
+    // Forgets an imported sound, everything using it goes back to the default sound, in every profile
+    pub fn remove_sound(&mut self, path: &str) {
+        self.custom_sounds.retain(|s| s.path != path);
+        let profiles = self.profiles.iter_mut().map(|p| (&mut p.friends, &mut p.rules));
+        let mut sounds: Vec<&mut Sound> = self.groups.iter_mut().map(|g| &mut g.sound).collect();
+        for (friends, rules) in std::iter::once((&mut self.friends, &mut self.rules)).chain(profiles) {
+            sounds.extend(friends.iter_mut().flat_map(|f| [&mut f.sound, &mut f.game_end_sound]));
+            sounds.extend(rules.iter_mut().filter_map(|r| match &mut r.action {
+                RuleAction::Sound(sound) => Some(sound),
+                _ => None,
+            }));
+        }
+        for sound in sounds.into_iter().filter(|s| s.path == path) {
+            *sound = Sound::default();
+        }
+    }
+
+    // Active profile first, then the stored ones
+    pub fn profile_names(&self) -> Vec<String> {
+        std::iter::once(self.profile.clone()).chain(self.profiles.iter().map(|p| p.name.clone())).collect()
+    }
+
+    // Stores the active profile and makes `name` the active one, returns false when there is no such profile
+    pub fn switch_profile(&mut self, name: &str) -> bool {
+        if name == self.profile {
+            return true;
+        }
+        let Some(index) = self.profiles.iter().position(|p| p.name == name) else {
+            return false;
+        };
+        let next = self.profiles.remove(index);
+        let current = self.take_profile(next);
+        self.profiles.insert(index, current);
+        true
+    }
+
+    // New profiles start without friends nor rules, with the flags of the active one
+    pub fn add_profile(&mut self, name: &str) -> bool {
+        if name.is_empty() || self.profile_names().iter().any(|n| n == name) {
+            return false;
+        }
+        let profile = Profile {
+            name: name.to_string(),
+            native_notification: self.native_notification,
+            notify_away_status: self.notify_away_status,
+            notify_in_game_status: self.notify_in_game_status,
+            volume: self.volume,
+            ..Default::default()
+        };
+        let current = self.take_profile(profile);
+        self.profiles.push(current);
+        true
+    }
+
+    // Deletes the active profile and switches to the first stored one, the last profile cannot be deleted
+    pub fn remove_active_profile(&mut self) {
+        if !self.profiles.is_empty() {
+            let next = self.profiles.remove(0);
+            self.take_profile(next);
+        }
+    }
+
+    // Replaces the active profile with `profile`, returns the previously active one
+    fn take_profile(&mut self, profile: Profile) -> Profile {
+        Profile {
+            name: std::mem::replace(&mut self.profile, profile.name),
+            friends: std::mem::replace(&mut self.friends, profile.friends),
+            rules: std::mem::replace(&mut self.rules, profile.rules),
+            native_notification: std::mem::replace(&mut self.native_notification, profile.native_notification),
+            notify_away_status: std::mem::replace(&mut self.notify_away_status, profile.notify_away_status),
+            notify_in_game_status: std::mem::replace(&mut self.notify_in_game_status, profile.notify_in_game_status),
+            volume: std::mem::replace(&mut self.volume, profile.volume),
+        }
+    }
+
+    pub fn hysteresis(&self) -> Hysteresis {
+        Hysteresis {
+            dwell: Duration::from_secs(self.status_dwell_secs as u64),
+            cooldown: Duration::from_secs(self.notify_cooldown_secs as u64),
+            missed_polls: self.missed_polls,
+        }
+    }
+}
+
+impl Sound {
+    pub fn builtins() -> impl Iterator<Item = Sound> {
+        crate::ASSET_SOUNDS.iter().map(|(label, path)| Sound {
+            label: label.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Friend {
+    // Friends are matched by puuid once resolved, otherwise by their riot id
+    pub fn matches(&self, api_friend: &ApiFriend) -> bool {
+        match &self.puuid {
+            Some(puuid) => puuid == &api_friend.puuid,
+            None => api_friend.is_named(&self.name),
+        }
+    }
+
+    // Stores the puuid the first time the friend is found and follows riot id changes afterwards
+    pub fn resolve_identity(&mut self, api_friend: &ApiFriend) {
+        if self.puuid.is_none() && !api_friend.puuid.is_empty() {
+            self.puuid = Some(api_friend.puuid.clone());
+        } else if !api_friend.is_named(&self.name) {
+            self.previous_name = Some(std::mem::replace(&mut self.name, api_friend.riot_id.clone()));
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            schema_version: crate::migrations::SCHEMA_VERSION,
+            friends: vec![Friend::default()],
+            groups: vec![],
+            rules: vec![],
+            native_notification: false,
+            volume: 100,
+            output_device: None,
+            muted: false,
+            custom_sounds: vec![],
+            notify_away_status: false,
+            notify_in_game_status: true,
+            status_dwell_secs: 5,
+            notify_cooldown_secs: 30,
+            missed_polls: 2,
+            history_retention_days: 30,
+            dark_mode: true,
+            profile: "Default".to_string(),
+            profiles: vec![],
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            name: "".to_string(),
+            friends: config.friends,
+            rules: config.rules,
+            native_notification: config.native_notification,
+            notify_away_status: config.notify_away_status,
+            notify_in_game_status: config.notify_in_game_status,
+            volume: config.volume,
+        }
+    }
+}
+
+impl Default for FriendGroup {
+    fn default() -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            name: "".to_string(),
+            sound: Sound::default(),
+            enabled: true,
+        }
+    }
+}
+
+impl Default for Friend {
+    fn default() -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            enabled: false,
+            name: "".to_string(),
+            puuid: None,
+            previous_name: None,
+            sound: Sound::default(),
+            volume: 100,
+            group: None,
+            notify_timer: 5,
+            is_repeat: false,
+            notify_game_end: false,
+            game_end_sound: Sound {
+                label: crate::ASSET_SOUNDS[1].0.to_string(),
+                path: crate::ASSET_SOUNDS[1].1.to_string(),
+            },
+            game_end_volume: 100,
+            game_end_message: "{name} finished their game".to_string(),
+            status: FriendStatus::default(),
+            presence: Presence::default(),
+            tracker: StatusTracker::default(),
+        }
+    }
+}
+
+// First built-in sound
+impl Default for Sound {
+    fn default() -> Self {
+        Self {
+            label: crate::ASSET_SOUNDS[0].0.to_string(),
+            path: crate::ASSET_SOUNDS[0].1.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_sounds_fall_back_to_the_default_one() {
+        let custom = Sound {
+            label: "Horn".to_string(),
+            path: "sounds/horn.ogg".to_string(),
+        };
+        let mut config = Config {
+            custom_sounds: vec![custom.clone()],
+            friends: vec![Friend {
+                sound: custom.clone(),
+                ..Default::default()
+            }],
+            rules: vec![Rule {
+                action: RuleAction::Sound(custom.clone()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(config.sounds().len(), crate::ASSET_SOUNDS.len() + 1);
+
+        config.remove_sound(&custom.path);
+        assert!(config.custom_sounds.is_empty());
+        assert_eq!(config.friends[0].sound, Sound::default());
+        assert_eq!(config.rules[0].action, RuleAction::Sound(Sound::default()));
+    }
+
+    #[test]
+    fn switching_profiles_swaps_friends_rules_and_flags() {
+        let mut config = Config {
+            friends: vec![Friend {
+                name: "Ahri#EUW".to_string(),
+                ..Default::default()
+            }],
+            rules: vec![Rule::default()],
+            volume: 40,
+            ..Default::default()
+        };
+        assert!(config.add_profile("Weekend"));
+        assert!(!config.add_profile("Default"));
+        assert_eq!(config.profile, "Weekend");
+        assert!(config.friends.iter().all(|f| f.name.is_empty()));
+        assert!(config.rules.is_empty());
+        assert_eq!(config.volume, 40);
+
+        config.volume = 80;
+        assert!(config.switch_profile("Default"));
+        assert!(!config.switch_profile("Ranked"));
+        assert_eq!(config.profile_names(), vec!["Default", "Weekend"]);
+        assert_eq!(config.friends[0].name, "Ahri#EUW");
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.volume, 40);
+
+        config.remove_active_profile();
+        assert_eq!(config.profile_names(), vec!["Weekend"]);
+        assert_eq!(config.volume, 80);
+        config.remove_active_profile();
+        assert_eq!(config.profile_names(), vec!["Weekend"]);
+    }
+
+    fn api_friend(riot_id: &str, group_name: &str) -> ApiFriend {
+        ApiFriend {
+            puuid: format!("{riot_id}-puuid"),
+            riot_id: riot_id.to_string(),
+            availability: "chat".to_string(),
+            group_name: group_name.to_string(),
+            presence: Presence::default(),
+        }
+    }
+
+    #[test]
+    fn added_friends_fill_the_empty_rows_of_their_group_first() {
+        let group = FriendGroup::default();
+        let mut config = Config {
+            friends: vec![
+                Friend::default(),
+                Friend {
+                    group: Some(group.uuid),
+                    ..Default::default()
+                },
+            ],
+            groups: vec![group.clone()],
+            ..Default::default()
+        };
+        config.add_friend(&api_friend("Ahri#EUW", ""), Some(group.uuid));
+        config.add_friend(&api_friend("Lux#EUW", ""), None);
+        assert_eq!(config.friends.len(), 2);
+        assert_eq!(config.friends[0].name, "Lux#EUW");
+        assert_eq!(config.friends[1].name, "Ahri#EUW");
+        assert_eq!(config.friends[1].puuid.as_deref(), Some("Ahri#EUW-puuid"));
+
+        // Without an empty row left a new one is added to the group
+        config.add_friend(&api_friend("Jinx#EUW", ""), Some(group.uuid));
+        assert_eq!(config.friends.len(), 3);
+        assert_eq!(config.friends[2].name, "Jinx#EUW");
+        assert_eq!(config.friends[2].group, Some(group.uuid));
+    }
+
+    #[test]
+    fn client_groups_are_imported_without_the_friends_already_tracked() {
+        let api_friends = vec![api_friend("Ahri#EUW", "Duo"), api_friend("Lux#EUW", "Duo"), api_friend("Jinx#EUW", "Flex")];
+        let mut config = Config {
+            friends: vec![Friend {
+                name: "Ahri#EUW".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.import_client_group("Duo", &api_friends);
+        assert_eq!(config.groups.len(), 1);
+        assert_eq!(config.groups[0].name, "Duo");
+        let names: Vec<&str> = config.friends.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Ahri#EUW", "Lux#EUW"]);
+        assert_eq!(config.friends[0].group, None);
+        assert_eq!(config.friends[1].group, Some(config.groups[0].uuid));
+
+        // Importing it again reuses the group and adds nobody
+        config.import_client_group("Duo", &api_friends);
+        assert_eq!(config.groups.len(), 1);
+        assert_eq!(config.friends.len(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::{Duration, Instant};
+
+use crate::presence::FriendStatus;
+
+/// Thresholds shared by every tracker, taken from the settings
+#[derive(Debug, Clone, Copy)]
+pub struct Hysteresis {
+    // How long a new status has to last before it is accepted
+    pub dwell: Duration,
+    // Minimum time between two notifications caused by status changes of the same friend
+    pub cooldown: Duration,
+    // Consecutive friends lists a friend has to be missing from before it is considered Offline
+    pub missed_polls: u8,
+}
+
+/// Per-friend state machine filtering out statuses that flicker for less than the dwell time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusTracker {
+    // Status waiting to be accepted and the moment it was first seen
+    pending: Option<(FriendStatus, Instant)>,
+    missed_polls: u8,
+    last_notified: Option<Instant>,
+}
+
+impl StatusTracker {
+    /// Records the status seen for the friend, returns the status to switch to once accepted
+    pub fn observe(&mut self, current: FriendStatus, seen: FriendStatus, now: Instant, h: Hysteresis) -> Option<FriendStatus> {
+        self.missed_polls = 0;
+        self.propose(current, seen, now, h)
+    }
+
+    /// Records the friend missing from a full friends list, a single miss is not enough to go Offline
+    pub fn observe_missing(&mut self, current: FriendStatus, now: Instant, h: Hysteresis) -> Option<FriendStatus> {
+        self.missed_polls = self.missed_polls.saturating_add(1);
+        match self.missed_polls >= h.missed_polls {
+            true => self.propose(current, FriendStatus::Offline, now, h),
+            false => None,
+        }
+    }
+
+    /// Accepts the pending status once it lasted the dwell time, called even when no new data comes in
+    pub fn tick(&mut self, now: Instant, h: Hysteresis) -> Option<FriendStatus> {
+        let (status, since) = self.pending?;
+        if now.saturating_duration_since(since) < h.dwell {
+            return None;
+        }
+        self.pending = None;
+        Some(status)
+    }
+
+    /// Time left before the pending status gets accepted
+    pub fn pending_remaining(&self, now: Instant, h: Hysteresis) -> Option<Duration> {
+        self.pending.map(|(_, since)| h.dwell.saturating_sub(now.saturating_duration_since(since)))
+    }
+
+    pub fn can_notify(&self, now: Instant, h: Hysteresis) -> bool {
+        self.last_notified.is_none_or(|last| now.saturating_duration_since(last) >= h.cooldown)
+    }
+
+    pub fn notified(&mut self, now: Instant) {
+        self.last_notified = Some(now);
+    }
+
+    /// Forgets pending changes, used when the status is forced (e.g. the client closed)
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.missed_polls = 0;
+    }
+
+    // Going back to the current status cancels the pending change, a different status restarts the dwell time
+    fn propose(&mut self, current: FriendStatus, seen: FriendStatus, now: Instant, h: Hysteresis) -> Option<FriendStatus> {
+        match self.pending {
+            _ if seen == current => {
+                self.pending = None;
+                return None;
+            }
+            Some((status, _)) if status == seen => {}
+            _ => self.pending = Some((seen, now)),
+        }
+        self.tick(now, h)
+    }
+}
+
+impl Default for Hysteresis {
+    fn default() -> Self {
+        Self {
+            dwell: Duration::from_secs(5),
+            cooldown: Duration::from_secs(30),
+            missed_polls: 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use FriendStatus::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn accepts_status_after_dwell_time() {
+        let (h, start) = (Hysteresis::default(), Instant::now());
+        let mut tracker = StatusTracker::default();
+        assert_eq!(tracker.observe(Offline, Online, start, h), None);
+        assert_eq!(tracker.tick(start + 4 * SECOND, h), None);
+        assert_eq!(tracker.observe(Offline, Online, start + 5 * SECOND, h), Some(Online));
+        assert_eq!(tracker.tick(start + 6 * SECOND, h), None);
+    }
+
+    #[test]
+    fn flapping_status_is_never_accepted() {
+        let (h, start) = (Hysteresis::default(), Instant::now());
+        let mut tracker = StatusTracker::default();
+        for i in 0..10 {
+            let seen = if i % 2 == 0 { Away } else { Online };
+            assert_eq!(tracker.observe(Online, seen, start + i * 3 * SECOND, h), None);
+        }
+        // A different status restarts the dwell time
+        assert_eq!(tracker.observe(Online, InGame, start + 30 * SECOND, h), None);
+        assert_eq!(tracker.observe(Online, Away, start + 33 * SECOND, h), None);
+        assert_eq!(tracker.tick(start + 38 * SECOND, h), Some(Away));
+    }
+
+    #[test]
+    fn goes_offline_after_missed_polls() {
+        let h = Hysteresis {
+            dwell: Duration::ZERO,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut tracker = StatusTracker::default();
+        assert_eq!(tracker.observe_missing(Online, start, h), None);
+        assert_eq!(tracker.observe(Online, Online, start + 3 * SECOND, h), None);
+        assert_eq!(tracker.observe_missing(Online, start + 6 * SECOND, h), None);
+        assert_eq!(tracker.observe_missing(Online, start + 9 * SECOND, h), Some(Offline));
+    }
+
+    #[test]
+    fn cooldown_after_notification() {
+        let (h, start) = (Hysteresis::default(), Instant::now());
+        let mut tracker = StatusTracker::default();
+        assert!(tracker.can_notify(start, h));
+        tracker.notified(start);
+        assert!(!tracker.can_notify(start + 29 * SECOND, h));
+        assert!(tracker.can_notify(start + 30 * SECOND, h));
+    }
+}
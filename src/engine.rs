@@ -0,0 +1,745 @@
+use notify_rust::Notification;
+use std::collections::HashMap;
+use std::sync::mpsc::{Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::{Config, Sound};
+use crate::history::{History, HistoryEntry, HistoryEvent};
+use crate::presence::{ApiFriend, FriendStatus, Presence};
+use crate::rules::{self, Cooldowns, Rule, RuleAction, Transition};
+use crate::scheduler::Scheduler;
+
+type Identity = (String, Option<String>, Option<String>);
+
+// The history is pruned on start and then periodically, in case the app runs for days
+const HISTORY_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Default)]
+pub enum Message {
+    ClientStatus(bool),
+    FriendStatus(Vec<ApiFriend>),
+    FriendPresence(ApiFriend),
+    // Puuid of a friend removed from the friends list, pushed by the websocket
+    FriendRemoved(String),
+    Notify(Uuid),
+    // Configuration edited in the gui, along with the revision of the edit
+    UpdateConfig(Box<Config>, u64),
+    // Toggles from the tray menu, sent on their own so they do not replace an edit the gui has queued
+    SetFriendEnabled(Uuid, bool),
+    SetMuted(bool),
+    // Sound file path and its volume in percent, scaled by the master volume
+    PlaySound(String, u8),
+    SetVolume(u8),
+    // Name of the preferred output device, None for the default one
+    SetOutputDevice(Option<String>),
+    // Sent when the app quits, answered once the engine stopped
+    Shutdown(Sender<()>),
+    #[default]
+    None,
+}
+
+/// State published by the engine after every handled message, the gui only draws from it
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    // Incremented on every publish
+    pub version: u64,
+    // Revision of the last configuration received from the gui
+    pub config_revision: u64,
+    pub config: Config,
+    // Latest friends list received from the client, feeds the friend pickers
+    pub api_friends: Vec<ApiFriend>,
+    pub client_status: bool,
+}
+
+/// Handle to the engine thread, used to send it messages and read the latest snapshot
+#[derive(Debug, Clone)]
+pub struct EngineHandle {
+    sx: Sender<Message>,
+    snapshot: Arc<Mutex<Snapshot>>,
+    scheduler: Scheduler,
+}
+
+/// Owns the tracked friends state and sends the notifications
+/// It runs on its own thread so notifying never depends on the gui being drawn
+pub struct Engine {
+    config: Config,
+    config_revision: u64,
+    api_friends: Vec<ApiFriend>,
+    client_status: bool,
+    rule_cooldowns: Cooldowns,
+    scheduler: Scheduler,
+    history: History,
+    history_pruned: Instant,
+    // Riot id, puuid and previous name of the friends in the last configuration received
+    // The gui keeps sending them until it takes a snapshot, even after the engine resolved a rename
+    identities: HashMap<Uuid, Identity>,
+    sx: Sender<Message>,
+    s_sx: Sender<Message>,
+}
+
+impl EngineHandle {
+    /// Sender used by the client threads to feed the engine
+    pub fn sender(&self) -> Sender<Message> {
+        self.sx.clone()
+    }
+
+    pub fn send(&self, msg: Message) {
+        let _ = self.sx.send(msg);
+    }
+
+    /// Latest snapshot if it is newer than `version`
+    pub fn snapshot_since(&self, version: u64) -> Option<Snapshot> {
+        let snapshot = self.snapshot.lock().unwrap();
+        (snapshot.version > version).then(|| snapshot.clone())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.snapshot.lock().unwrap().clone()
+    }
+
+    /// Stops the engine once the friends that are not offline are recorded as going offline, so their sessions end with the app
+    pub fn shutdown(&self) {
+        let (sx, rx) = channel();
+        if self.sx.send(Message::Shutdown(sx)).is_ok() {
+            let _ = rx.recv_timeout(Duration::from_secs(1));
+        }
+    }
+
+    /// Time left before the next reminder of the friend
+    pub fn reminder_remaining(&self, friend: Uuid) -> Option<Duration> {
+        self.scheduler.remaining(friend)
+    }
+}
+
+impl Engine {
+    /// Starts the engine thread, `on_change` is called every time a new snapshot is published
+    /// Presence transitions and notifications are recorded to `history`
+    pub fn start(config: Config, s_sx: Sender<Message>, history: History, on_change: impl Fn() + Send + 'static) -> EngineHandle {
+        let (sx, rx) = channel::<Message>();
+        let scheduler = Scheduler::start(sx.clone());
+        let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+        let mut engine = Engine {
+            identities: identities(&config),
+            config,
+            config_revision: 0,
+            api_friends: vec![],
+            client_status: false,
+            rule_cooldowns: Cooldowns::new(),
+            scheduler: scheduler.clone(),
+            history,
+            history_pruned: Instant::now(),
+            sx: sx.clone(),
+            s_sx,
+        };
+        engine.history.prune(engine.config.history_retention_days);
+        engine.publish(&snapshot);
+        let _ = engine.s_sx.send(Message::SetVolume(engine.config.volume));
+        let _ = engine.s_sx.send(Message::SetOutputDevice(engine.config.output_device.clone()));
+
+        let shared = snapshot.clone();
+        thread::spawn(move || {
+            loop {
+                // Pending status changes are accepted once they lasted the dwell time, even without new data
+                let received = match engine.next_pending() {
+                    Some(timeout) => rx.recv_timeout(timeout).ok(),
+                    None => rx.recv().ok(),
+                };
+                if let Some(Message::Shutdown(done)) = received {
+                    engine.set_offline();
+                    let _ = done.send(());
+                    return;
+                }
+                if let Some(msg) = received {
+                    engine.handle_message(msg);
+                }
+                engine.tick();
+                engine.publish(&shared);
+                on_change();
+            }
+        });
+        EngineHandle { sx, snapshot, scheduler }
+    }
+
+    fn publish(&self, snapshot: &Mutex<Snapshot>) {
+        let mut snapshot = snapshot.lock().unwrap();
+        *snapshot = Snapshot {
+            version: snapshot.version + 1,
+            config_revision: self.config_revision,
+            config: self.config.clone(),
+            api_friends: self.api_friends.clone(),
+            client_status: self.client_status,
+        };
+    }
+
+    fn handle_message(&mut self, msg: Message) {
+        match msg {
+            // Update friend status from the full friends list
+            Message::FriendStatus(fr) => {
+                for i in 0..self.config.friends.len() {
+                    if let Some(api_friend) = fr.iter().find(|_f| self.config.friends[i].matches(_f)) {
+                        self.config.friends[i].resolve_identity(api_friend);
+                        self.config.friends[i].presence = api_friend.presence.clone();
+                        self.observe_friend_status(i, Some(api_friend.into()));
+                    } else {
+                        // Friends not found in the API response go Offline after a few missed responses
+                        self.observe_friend_status(i, None);
+                    }
+                }
+                self.api_friends = fr;
+            }
+            // A single friend presence changed, pushed by the websocket
+            Message::FriendPresence(api_friend) => {
+                for i in 0..self.config.friends.len() {
+                    if self.config.friends[i].matches(&api_friend) {
+                        self.config.friends[i].resolve_identity(&api_friend);
+                        self.config.friends[i].presence = api_friend.presence.clone();
+                        self.observe_friend_status(i, Some((&api_friend).into()));
+                    }
+                }
+                match self.api_friends.iter_mut().find(|f| f.puuid == api_friend.puuid) {
+                    Some(f) => *f = api_friend,
+                    None => self.api_friends.push(api_friend),
+                }
+            }
+            // Friends removed from the friends list are seen Offline, like when missing from a full list
+            Message::FriendRemoved(puuid) => {
+                for i in 0..self.config.friends.len() {
+                    if self.config.friends[i].puuid.as_ref() == Some(&puuid) {
+                        self.observe_friend_status(i, Some(FriendStatus::Offline));
+                    }
+                }
+                self.api_friends.retain(|f| f.puuid != puuid);
+            }
+            Message::ClientStatus(status) => {
+                // Set all friends statuses to Offline, this is needed in case the client is closed after retrieving some statuses
+                if !status {
+                    self.set_offline();
+                }
+                if status != self.client_status {
+                    info!("League client {}", if status { "connected" } else { "disconnected" });
+                }
+                self.client_status = status;
+            }
+            // Sent on status changes and by the scheduler when a reminder is due, conditions might have changed meanwhile
+            Message::Notify(uuid) => {
+                let mut notified = None;
+                if let Some(friend) = self.config.friends.iter().find(|f| f.uuid == uuid)
+                    && friend.enabled
+                    && self.config.is_group_enabled(friend.group)
+                {
+                    // Friend must still be in a status worth notifying, reminders stop once it is not
+                    match (&friend.status, self.config.notify_away_status, self.config.notify_in_game_status) {
+                        (FriendStatus::Online, _, _) | (FriendStatus::Away, true, _) | (FriendStatus::InGame, _, true) => {
+                            // Handle repeating the notification, the next reminder replaces any pending one
+                            if friend.is_repeat {
+                                self.scheduler.schedule(friend.uuid, Duration::from_secs(friend.notify_timer as u64));
+                            }
+                            // Now that conditions are met, play the sound associated with this Friend
+                            // and send the windows notification, describing the game when there is one
+                            let body = friend.presence.describe(&friend.name).unwrap_or(format!("{} is Online!", friend.name));
+                            self.send_notification(&friend.sound, friend.volume, &body);
+                            self.record(friend.uuid, &friend.name, HistoryEvent::Notification { body });
+                            notified = Some(friend.uuid);
+                        }
+                        _ => {}
+                    }
+                };
+                // Starts the cooldown of status change notifications
+                if let Some(friend) = notified.and_then(|uuid| self.config.friends.iter_mut().find(|f| f.uuid == uuid)) {
+                    friend.tracker.notified(Instant::now());
+                }
+            }
+            Message::UpdateConfig(config, revision) => self.apply_config(*config, revision),
+            // Same as toggling the friend in the gui, enabling it notifies right away
+            Message::SetFriendEnabled(uuid, enabled) => {
+                let Some(friend) = self.config.friends.iter_mut().find(|f| f.uuid == uuid && f.enabled != enabled) else {
+                    return;
+                };
+                friend.enabled = enabled;
+                self.scheduler.cancel(uuid);
+                if enabled {
+                    self.handle_message(Message::Notify(uuid));
+                }
+            }
+            Message::SetMuted(muted) => self.config.muted = muted,
+            _ => {}
+        }
+    }
+
+    // Replaces the configuration with the one edited in the gui, the runtime state of the friends and the renames it missed are kept
+    // Enabling a friend or its repeat notifies right away, reminders of friends toggled off or removed are cancelled
+    fn apply_config(&mut self, mut config: Config, revision: u64) {
+        let mut notify = vec![];
+        let sent = identities(&config);
+        for f in config.friends.iter_mut() {
+            match self.config.friends.iter().find(|old| old.uuid == f.uuid) {
+                Some(old) => {
+                    // Identities left as they were sent last time are the engine ones, possibly renamed meanwhile
+                    if self.identities.get(&f.uuid) == sent.get(&f.uuid) {
+                        f.name = old.name.clone();
+                        f.puuid = old.puuid.clone();
+                        f.previous_name = old.previous_name.clone();
+                    }
+                    f.status = old.status;
+                    f.presence = old.presence.clone();
+                    f.tracker = old.tracker.clone();
+                    if f.enabled != old.enabled || f.is_repeat != old.is_repeat {
+                        self.scheduler.cancel(f.uuid);
+                        if (f.enabled && !old.enabled) || (f.is_repeat && !old.is_repeat) {
+                            notify.push(f.uuid);
+                        }
+                    }
+                }
+                // Friends just added start from their current status without notifying
+                None => {
+                    if let Some(api_friend) = self.api_friends.iter().find(|a| f.matches(a)) {
+                        f.status = api_friend.into();
+                        f.presence = api_friend.presence.clone();
+                    }
+                }
+            }
+        }
+        for old in self.config.friends.iter().filter(|old| !config.friends.iter().any(|f| f.uuid == old.uuid)) {
+            self.scheduler.cancel(old.uuid);
+        }
+        // Another profile starts over, its friends kept the state they had when it was switched away from
+        // Reminders of the previous friends are cancelled above since they are no longer in the list
+        if config.profile != self.config.profile {
+            self.rule_cooldowns.clear();
+            for f in config.friends.iter_mut() {
+                f.tracker = Default::default();
+                if !self.api_friends.iter().any(|a| f.matches(a)) {
+                    f.status = FriendStatus::Offline;
+                    f.presence = Presence::default();
+                }
+            }
+        }
+        if config.volume != self.config.volume {
+            let _ = self.s_sx.send(Message::SetVolume(config.volume));
+        }
+        if config.output_device != self.config.output_device {
+            let _ = self.s_sx.send(Message::SetOutputDevice(config.output_device.clone()));
+        }
+        if config.history_retention_days != self.config.history_retention_days {
+            self.history.prune(config.history_retention_days);
+        }
+        self.config = config;
+        self.config_revision = revision;
+        self.identities = sent;
+        for uuid in notify {
+            self.handle_message(Message::Notify(uuid));
+        }
+    }
+
+    // Feeds the status seen in the client to the friend tracker, None when the friend is missing from the friends list
+    // The status only changes once the tracker accepts it
+    fn observe_friend_status(&mut self, index: usize, seen: Option<FriendStatus>) {
+        let (h, now) = (self.config.hysteresis(), Instant::now());
+        let f = &mut self.config.friends[index];
+        let accepted = match seen {
+            Some(status) => f.tracker.observe(f.status, status, now, h),
+            None => f.tracker.observe_missing(f.status, now, h),
+        };
+        if let Some(status) = accepted {
+            if status == FriendStatus::Offline {
+                f.presence = Presence::default();
+            }
+            self.update_friend_status(index, status);
+        }
+    }
+
+    fn tick(&mut self) {
+        let (h, now) = (self.config.hysteresis(), Instant::now());
+        if now.duration_since(self.history_pruned) >= HISTORY_PRUNE_INTERVAL {
+            self.history.prune(self.config.history_retention_days);
+            self.history_pruned = now;
+        }
+        for i in 0..self.config.friends.len() {
+            if let Some(status) = self.config.friends[i].tracker.tick(now, h) {
+                self.update_friend_status(i, status);
+            }
+        }
+    }
+
+    // Time left before the next pending status gets accepted
+    fn next_pending(&self) -> Option<Duration> {
+        let (h, now) = (self.config.hysteresis(), Instant::now());
+        self.config.friends.iter().filter_map(|f| f.tracker.pending_remaining(now, h)).min()
+    }
+
+    // Updates the friend status, sends a notification if it transitioned to an active state and runs the matching rules
+    fn update_friend_status(&mut self, index: usize, new_status: FriendStatus) {
+        let now = Instant::now();
+        let f = &self.config.friends[index];
+        let old_status = f.status;
+        let is_notifying = f.enabled && self.config.is_group_enabled(f.group);
+        // Status changes right after a notification are not notified again
+        let is_cooled_down = f.tracker.can_notify(now, self.config.hysteresis());
+
+        let should_notify =
+            is_notifying && is_cooled_down && rules::is_default_transition(old_status, new_status, self.config.notify_away_status, self.config.notify_in_game_status);
+        // Leaving a game is the best moment to invite, it replaces the regular notification for this transition
+        let has_game_ended =
+            is_notifying && is_cooled_down && f.notify_game_end && old_status == FriendStatus::InGame && matches!(new_status, FriendStatus::Online | FriendStatus::Away);
+
+        // Always update the friend's status to reflect the latest data.
+        self.config.friends[index].status = new_status;
+        if has_game_ended {
+            self.config.friends[index].tracker.notified(now);
+        }
+        let f = &self.config.friends[index];
+        info!("{} is now {}", f.name, new_status.label());
+        self.record(f.uuid, &f.name, HistoryEvent::Status { from: old_status, to: new_status });
+        if has_game_ended {
+            let body = f.game_end_message.replace("{name}", &f.name);
+            self.send_notification(&f.game_end_sound, f.game_end_volume, &body);
+            self.record(f.uuid, &f.name, HistoryEvent::Notification { body });
+        } else if should_notify {
+            let _ = self.sx.send(Message::Notify(f.uuid));
+        }
+
+        if !is_notifying {
+            return;
+        }
+        let transition = Transition {
+            friend: f.uuid,
+            group: f.group,
+            name: &f.name,
+            from: old_status,
+            to: new_status,
+        };
+        let fired: Vec<Uuid> = rules::evaluate(&self.config.rules, &transition, &self.rule_cooldowns, now)
+            .into_iter()
+            .map(|rule| {
+                info!("{} rule fired for {}", rule.action.label(), transition.name);
+                self.run_rule_action(rule, &transition);
+                let action = rule.action.label().to_string();
+                let summary = rule.summary(&self.config.friends, &self.config.groups);
+                self.record(transition.friend, transition.name, HistoryEvent::Rule { rule: rule.uuid, action, summary });
+                rule.uuid
+            })
+            .collect();
+        let friend = f.uuid;
+        for rule in fired {
+            self.rule_cooldowns.insert((rule, friend), now);
+        }
+    }
+
+    fn run_rule_action(&self, rule: &Rule, transition: &Transition) {
+        match &rule.action {
+            RuleAction::Sound(sound) => self.play_sound(sound, rule.volume),
+            RuleAction::Toast(message) => show_toast(&transition.format(message)),
+            RuleAction::Webhook(url) if !url.is_empty() => rules::post_webhook(url, transition),
+            RuleAction::Command(command) if !command.is_empty() => rules::run_command(command, transition),
+            _ => {}
+        }
+    }
+
+    // Plays the sound and shows the native notification if enabled
+    fn send_notification(&self, sound: &Sound, volume: u8, body: &str) {
+        info!("Notification: {body}");
+        self.play_sound(sound, volume);
+        if self.config.native_notification {
+            show_toast(body);
+        };
+    }
+
+    // Every friend goes Offline, recorded so the sessions in the history end with the client or the app
+    fn set_offline(&mut self) {
+        for f in self.config.friends.iter().filter(|f| f.status != FriendStatus::Offline) {
+            self.record(
+                f.uuid,
+                &f.name,
+                HistoryEvent::Status {
+                    from: f.status,
+                    to: FriendStatus::Offline,
+                },
+            );
+        }
+        for f in self.config.friends.iter_mut() {
+            f.status = FriendStatus::Offline;
+            f.presence = Presence::default();
+            f.tracker.reset();
+        }
+        self.api_friends.clear();
+    }
+
+    fn record(&self, friend: Uuid, name: &str, event: HistoryEvent) {
+        self.history.append(&HistoryEntry::new(friend, name, event));
+    }
+
+    fn play_sound(&self, sound: &Sound, volume: u8) {
+        if !self.config.muted {
+            let _ = self.s_sx.send(Message::PlaySound(sound.path.clone(), volume));
+        }
+    }
+}
+
+fn identities(config: &Config) -> HashMap<Uuid, Identity> {
+    config
+        .friends
+        .iter()
+        .map(|f| (f.uuid, (f.name.clone(), f.puuid.clone(), f.previous_name.clone())))
+        .collect()
+}
+
+fn show_toast(body: &str) {
+    let _ = Notification::new()
+        .appname("Friends Notifier")
+        .timeout(Duration::from_millis(5000))
+        .body(body)
+        .auto_icon()
+        .finalize()
+        .show();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Friend;
+    use crate::debounce::StatusTracker;
+
+    fn engine(config: Config) -> (Engine, std::sync::mpsc::Receiver<Message>) {
+        let (sx, _) = channel();
+        let (s_sx, s_rx) = channel();
+        let engine = Engine {
+            identities: identities(&config),
+            config,
+            config_revision: 0,
+            api_friends: vec![],
+            client_status: true,
+            rule_cooldowns: Cooldowns::new(),
+            scheduler: Scheduler::default(),
+            history: History::default(),
+            history_pruned: Instant::now(),
+            sx,
+            s_sx,
+        };
+        (engine, s_rx)
+    }
+
+    #[test]
+    fn config_updates_keep_runtime_state_and_notify_enabled_friends() {
+        let friend = Friend {
+            status: FriendStatus::Online,
+            ..Default::default()
+        };
+        let (mut engine, s_rx) = engine(Config {
+            friends: vec![friend.clone()],
+            ..Default::default()
+        });
+
+        // The gui copy has no runtime state, enabling the friend notifies right away
+        let mut edited = Config {
+            friends: vec![Friend {
+                enabled: true,
+                status: FriendStatus::Offline,
+                ..friend
+            }],
+            ..Default::default()
+        };
+        engine.apply_config(edited.clone(), 1);
+        assert_eq!(engine.config_revision, 1);
+        assert_eq!(engine.config.friends[0].status, FriendStatus::Online);
+        assert!(matches!(s_rx.try_recv(), Ok(Message::PlaySound(_, 100))));
+
+        // Edits not touching the toggles do not notify again
+        edited.friends[0].notify_timer = 30;
+        engine.apply_config(edited, 2);
+        assert_eq!(engine.config.friends[0].notify_timer, 30);
+        assert!(s_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn tray_toggles_notify_like_gui_edits_without_taking_a_revision() {
+        let friend = Friend {
+            status: FriendStatus::Online,
+            ..Default::default()
+        };
+        let (mut engine, s_rx) = engine(Config {
+            friends: vec![friend.clone()],
+            ..Default::default()
+        });
+        engine.handle_message(Message::SetFriendEnabled(friend.uuid, true));
+        assert!(engine.config.friends[0].enabled);
+        assert!(matches!(s_rx.try_recv(), Ok(Message::PlaySound(_, 100))));
+
+        // Muted notifications are not played
+        engine.handle_message(Message::SetMuted(true));
+        engine.handle_message(Message::SetFriendEnabled(friend.uuid, false));
+        engine.handle_message(Message::SetFriendEnabled(friend.uuid, true));
+        assert!(engine.config.muted);
+        assert!(s_rx.try_recv().is_err());
+        assert_eq!(engine.config_revision, 0);
+    }
+
+    #[test]
+    fn game_end_is_notified_only_to_friends_that_opted_in() {
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", Uuid::new_v4()));
+        let in_game = Friend {
+            enabled: true,
+            status: FriendStatus::InGame,
+            ..Default::default()
+        };
+        let opted_in = Friend {
+            name: "Ahri#EUW".to_string(),
+            notify_game_end: true,
+            game_end_message: "{name} left their game".to_string(),
+            game_end_volume: 40,
+            ..in_game.clone()
+        };
+        let (mut engine, s_rx) = engine(Config {
+            friends: vec![
+                opted_in.clone(),
+                Friend {
+                    name: "Lux#EUW".to_string(),
+                    ..in_game.clone()
+                },
+                Friend {
+                    name: "Jinx#EUW".to_string(),
+                    ..opted_in.clone()
+                },
+                Friend {
+                    name: "Zed#EUW".to_string(),
+                    enabled: false,
+                    ..opted_in.clone()
+                },
+            ],
+            ..Default::default()
+        });
+        engine.history = History::new(Some(path.clone()));
+
+        engine.update_friend_status(0, FriendStatus::Online);
+        match s_rx.try_recv() {
+            Ok(Message::PlaySound(sound, volume)) => {
+                assert_eq!(sound, opted_in.game_end_sound.path);
+                assert_eq!(volume, 40);
+            }
+            other => panic!("expected the game end sound, got {other:?}"),
+        }
+
+        // Not opted in, leaving the game with the client, and disabled
+        engine.update_friend_status(1, FriendStatus::Online);
+        engine.update_friend_status(2, FriendStatus::Offline);
+        engine.update_friend_status(3, FriendStatus::Away);
+        assert!(s_rx.try_recv().is_err());
+
+        let notified: Vec<HistoryEvent> = engine
+            .history
+            .load()
+            .into_iter()
+            .map(|e| e.event)
+            .filter(|e| matches!(e, HistoryEvent::Notification { .. }))
+            .collect();
+        assert_eq!(
+            notified,
+            vec![HistoryEvent::Notification {
+                body: "Ahri#EUW left their game".to_string()
+            }]
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn switching_profiles_starts_the_notifications_over() {
+        let now = Instant::now();
+        let ahri = Friend {
+            name: "Ahri#EUW".to_string(),
+            enabled: true,
+            status: FriendStatus::Online,
+            ..Default::default()
+        };
+        let mut config = Config {
+            friends: vec![ahri.clone()],
+            rules: vec![Rule::default()],
+            ..Default::default()
+        };
+        let (mut engine, _s_rx) = engine(config.clone());
+        engine.scheduler.schedule(ahri.uuid, Duration::from_secs(60));
+        engine.rule_cooldowns.insert((config.rules[0].uuid, ahri.uuid), now);
+        engine.config.friends[0].tracker.notified(now);
+
+        // The gui copy holds the engine state it last took, stored along with the profile
+        config.friends[0].tracker.notified(now);
+        assert!(config.add_profile("Weekend"));
+        engine.apply_config(config.clone(), 1);
+        assert_eq!(engine.scheduler.remaining(ahri.uuid), None);
+        assert!(engine.rule_cooldowns.is_empty());
+
+        assert!(config.switch_profile("Default"));
+        engine.apply_config(config, 2);
+        let f = &engine.config.friends[0];
+        assert_eq!(f.uuid, ahri.uuid);
+        assert_eq!(f.status, FriendStatus::Offline);
+        assert_eq!(f.tracker, StatusTracker::default());
+    }
+
+    #[test]
+    fn renames_resolved_by_the_engine_survive_stale_gui_edits() {
+        let friend = Friend {
+            name: "Ahri#EUW".to_string(),
+            puuid: Some("3b1e5c7a-puuid".to_string()),
+            ..Default::default()
+        };
+        let gui = Config {
+            friends: vec![friend.clone()],
+            ..Default::default()
+        };
+        let (mut engine, _s_rx) = engine(gui.clone());
+        let renamed = ApiFriend {
+            puuid: "3b1e5c7a-puuid".to_string(),
+            riot_id: "Kumiho#EUW".to_string(),
+            availability: "chat".to_string(),
+            group_name: "".to_string(),
+            presence: Presence::default(),
+        };
+        engine.handle_message(Message::FriendPresence(renamed));
+        assert_eq!(engine.config.friends[0].name, "Kumiho#EUW");
+
+        // Edited before the gui took the snapshot with the new name
+        let mut edited = gui;
+        edited.friends[0].notify_timer = 30;
+        engine.apply_config(edited.clone(), 1);
+        assert_eq!(engine.config.friends[0].notify_timer, 30);
+        assert_eq!(engine.config.friends[0].name, "Kumiho#EUW");
+        assert_eq!(engine.config.friends[0].previous_name.as_deref(), Some("Ahri#EUW"));
+
+        // Names typed in the gui are taken
+        edited.friends[0].name = "Lux#EUW".to_string();
+        engine.apply_config(edited, 2);
+        assert_eq!(engine.config.friends[0].name, "Lux#EUW");
+    }
+
+    #[test]
+    fn friends_still_online_are_recorded_offline_when_stopping() {
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", Uuid::new_v4()));
+        let online = Friend {
+            name: "Ahri#EUW".to_string(),
+            status: FriendStatus::InGame,
+            ..Default::default()
+        };
+        let (mut engine, _s_rx) = engine(Config {
+            friends: vec![online.clone(), Friend::default()],
+            ..Default::default()
+        });
+        engine.history = History::new(Some(path.clone()));
+
+        engine.set_offline();
+        let entries = engine.history.load();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].friend, online.uuid);
+        assert_eq!(
+            entries[0].event,
+            HistoryEvent::Status {
+                from: FriendStatus::InGame,
+                to: FriendStatus::Offline
+            }
+        );
+        assert!(engine.config.friends.iter().all(|f| f.status == FriendStatus::Offline));
+        let _ = std::fs::remove_file(path);
+    }
+}
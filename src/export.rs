@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::audio;
+use crate::config::{Config, Friend, FriendGroup, Sound};
+use crate::rules::{Rule, RuleAction, RuleTarget};
+
+/// Version of the export format, files from newer versions are refused
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Shareable part of the configuration, written as a versioned json file
+/// Machine specific settings such as the output device or the theme are left out
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConfigExport {
+    pub version: u32,
+    pub app_version: String,
+    pub friends: Vec<Friend>,
+    pub groups: Vec<FriendGroup>,
+    pub rules: Vec<Rule>,
+    pub custom_sounds: Vec<Sound>,
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)] // new fields get default values when reading older exports
+pub struct Settings {
+    pub native_notification: bool,
+    pub volume: u8,
+    pub notify_away_status: bool,
+    pub notify_in_game_status: bool,
+    pub status_dwell_secs: u16,
+    pub notify_cooldown_secs: u16,
+    pub missed_polls: u8,
+    pub history_retention_days: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportError {
+    Invalid,
+    NewerVersion(u32),
+}
+
+/// What an import changed, shown once it is done
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added_friends: usize,
+    // Friends already tracked under the same riot id, the local ones are kept
+    pub skipped_friends: usize,
+    pub added_rules: usize,
+    // Command and webhook rules among the added ones, imported disabled until the user reviews them
+    pub disabled_rules: usize,
+    // References to sound files missing from the managed sounds directory, replaced with the default sound
+    pub missing_sounds: usize,
+}
+
+impl ImportError {
+    pub fn label(&self) -> String {
+        match self {
+            ImportError::Invalid => "Not a valid export file".to_string(),
+            ImportError::NewerVersion(v) => format!("Exported by a newer version of the app (format {v})"),
+        }
+    }
+}
+
+impl ImportSummary {
+    pub fn label(&self) -> String {
+        format!(
+            "{} friends added, {} already tracked, {} rules added ({} command or webhook rules left disabled), {} missing sounds",
+            self.added_friends, self.skipped_friends, self.added_rules, self.disabled_rules, self.missing_sounds
+        )
+    }
+}
+
+impl Settings {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            native_notification: config.native_notification,
+            volume: config.volume,
+            notify_away_status: config.notify_away_status,
+            notify_in_game_status: config.notify_in_game_status,
+            status_dwell_secs: config.status_dwell_secs,
+            notify_cooldown_secs: config.notify_cooldown_secs,
+            missed_polls: config.missed_polls,
+            history_retention_days: config.history_retention_days,
+        }
+    }
+
+    fn apply(&self, config: &mut Config) {
+        config.native_notification = self.native_notification;
+        config.volume = self.volume;
+        config.notify_away_status = self.notify_away_status;
+        config.notify_in_game_status = self.notify_in_game_status;
+        config.status_dwell_secs = self.status_dwell_secs;
+        config.notify_cooldown_secs = self.notify_cooldown_secs;
+        config.missed_polls = self.missed_polls;
+        config.history_retention_days = self.history_retention_days;
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+impl ConfigExport {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            app_version: crate::APP_VERSION.to_string(),
+            // Empty rows are not worth sharing
+            friends: config.friends.iter().filter(|f| !f.name.is_empty()).cloned().collect(),
+            groups: config.groups.clone(),
+            rules: config.rules.clone(),
+            custom_sounds: config.custom_sounds.clone(),
+            settings: Settings::from_config(config),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, ImportError> {
+        let value: serde_json::Value = serde_json::from_str(s).map_err(|_| ImportError::Invalid)?;
+        let version = value.get("version").and_then(|v| v.as_u64()).ok_or(ImportError::Invalid)? as u32;
+        if version > EXPORT_VERSION {
+            return Err(ImportError::NewerVersion(version));
+        }
+        serde_json::from_value(value).map_err(|_| ImportError::Invalid)
+    }
+
+    /// Merges the export into the configuration
+    /// Friends are matched by riot id and groups by name, the local ones are kept and the imported rules follow them
+    /// Sounds whose file is missing here are replaced with the default one, settings are only applied on request
+    /// Imported sounds are only kept when they point into the managed `sounds_dir`, so deleting them never removes other files
+    pub fn merge_into(self, config: &mut Config, apply_settings: bool, sounds_dir: Option<&Path>) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+        for sound in self.custom_sounds {
+            if !config.custom_sounds.iter().any(|s| s.path == sound.path) && sounds_dir.is_some_and(|dir| audio::is_managed_sound(&sound.path, dir)) {
+                config.custom_sounds.push(sound);
+            }
+        }
+        let known = config.sounds();
+        let mut missing_sounds = 0;
+        let mut resolve = |sound: Sound| match known.iter().any(|s| s.path == sound.path) {
+            true => sound,
+            false => {
+                missing_sounds += 1;
+                Sound::default()
+            }
+        };
+
+        // Imported uuids mapped to the local ones
+        let mut groups: Vec<(Uuid, Uuid)> = vec![];
+        for group in self.groups {
+            let uuid = match config.groups.iter().find(|g| g.name == group.name) {
+                Some(local) => local.uuid,
+                None => {
+                    let uuid = fresh_uuid(group.uuid, config.groups.iter().map(|g| g.uuid));
+                    config.groups.push(FriendGroup {
+                        uuid,
+                        sound: resolve(group.sound.clone()),
+                        ..group.clone()
+                    });
+                    uuid
+                }
+            };
+            groups.push((group.uuid, uuid));
+        }
+        let local_group = |group: Option<Uuid>| group.and_then(|g| groups.iter().find(|(from, _)| *from == g).map(|(_, to)| *to));
+
+        let mut friends: Vec<(Uuid, Uuid)> = vec![];
+        for friend in self.friends.into_iter().filter(|f| !f.name.is_empty()) {
+            let duplicate = config
+                .friends
+                .iter()
+                .find(|f| f.name.to_lowercase() == friend.name.to_lowercase() || (f.puuid.is_some() && f.puuid == friend.puuid));
+            let uuid = match duplicate {
+                Some(local) => {
+                    summary.skipped_friends += 1;
+                    local.uuid
+                }
+                None => {
+                    summary.added_friends += 1;
+                    let uuid = fresh_uuid(friend.uuid, config.friends.iter().map(|f| f.uuid));
+                    let group = local_group(friend.group);
+                    let (sound, game_end_sound) = (resolve(friend.sound.clone()), resolve(friend.game_end_sound.clone()));
+                    config.friends.push(Friend {
+                        uuid,
+                        group,
+                        sound,
+                        game_end_sound,
+                        ..friend.clone()
+                    });
+                    uuid
+                }
+            };
+            friends.push((friend.uuid, uuid));
+        }
+
+        for mut rule in self.rules {
+            rule.target = match rule.target {
+                RuleTarget::Anyone => RuleTarget::Anyone,
+                RuleTarget::Friend(f) => match friends.iter().find(|(from, _)| *from == f) {
+                    Some((_, to)) => RuleTarget::Friend(*to),
+                    None => continue,
+                },
+                RuleTarget::Group(g) => match local_group(Some(g)) {
+                    Some(to) => RuleTarget::Group(to),
+                    None => continue,
+                },
+            };
+            if let RuleAction::Sound(sound) = rule.action {
+                rule.action = RuleAction::Sound(resolve(sound));
+            }
+            // The same rule imported twice is only added once, whether it was enabled since or not
+            if config.rules.iter().any(|r| {
+                Rule {
+                    uuid: r.uuid,
+                    enabled: r.enabled,
+                    ..rule.clone()
+                } == *r
+            }) {
+                continue;
+            }
+            // Shared files could run any command or send presences anywhere, these rules have to be enabled by hand
+            if matches!(rule.action, RuleAction::Command(_) | RuleAction::Webhook(_)) {
+                rule.enabled = false;
+                summary.disabled_rules += 1;
+            }
+            rule.uuid = fresh_uuid(rule.uuid, config.rules.iter().map(|r| r.uuid));
+            config.rules.push(rule);
+            summary.added_rules += 1;
+        }
+
+        if apply_settings {
+            self.settings.apply(config);
+        }
+        summary.missing_sounds = missing_sounds;
+        summary
+    }
+}
+
+// Keeps the imported uuid unless it is already used locally
+fn fresh_uuid(uuid: Uuid, mut used: impl Iterator<Item = Uuid>) -> Uuid {
+    match used.any(|u| u == uuid) {
+        true => Uuid::new_v4(),
+        false => uuid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_skip_tracked_friends_and_missing_sounds() {
+        // Existing files outside of the managed sounds directory are not accepted either
+        let outside = std::env::temp_dir().join(format!("horn-{}.ogg", Uuid::new_v4()));
+        std::fs::write(&outside, b"").unwrap();
+        let missing = Sound {
+            label: "Horn".to_string(),
+            path: outside.to_string_lossy().to_string(),
+        };
+        let group = FriendGroup {
+            name: "Team".to_string(),
+            ..Default::default()
+        };
+        let (ahri, lux) = (Friend::default(), Friend::default());
+        let shared = Config {
+            groups: vec![group.clone()],
+            friends: vec![
+                Friend {
+                    name: "Ahri#EUW".to_string(),
+                    ..ahri.clone()
+                },
+                Friend {
+                    name: "Lux#EUW".to_string(),
+                    group: Some(group.uuid),
+                    sound: missing.clone(),
+                    ..lux.clone()
+                },
+            ],
+            rules: vec![Rule {
+                target: RuleTarget::Friend(lux.uuid),
+                ..Default::default()
+            }],
+            custom_sounds: vec![missing],
+            volume: 40,
+            ..Default::default()
+        };
+        let file = ConfigExport::new(&shared).to_json();
+
+        let mut config = Config {
+            friends: vec![Friend {
+                name: "ahri#euw".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let summary = ConfigExport::from_json(&file).unwrap().merge_into(&mut config, false, None);
+        assert_eq!(summary.added_friends, 1);
+        assert_eq!(summary.skipped_friends, 1);
+        assert_eq!(summary.added_rules, 1);
+        assert_eq!(summary.missing_sounds, 1);
+        assert!(config.custom_sounds.is_empty());
+        assert_eq!(config.volume, 100);
+
+        let imported = config.friends.iter().find(|f| f.name == "Lux#EUW").unwrap();
+        assert_eq!(imported.sound, Sound::default());
+        assert_eq!(imported.group, Some(config.groups[0].uuid));
+        assert_eq!(config.rules[0].target, RuleTarget::Friend(imported.uuid));
+
+        // Importing the same file again only adds what is missing
+        let summary = ConfigExport::from_json(&file).unwrap().merge_into(&mut config, true, None);
+        assert_eq!((summary.added_friends, summary.added_rules), (0, 0));
+        assert_eq!(config.groups.len(), 1);
+        assert_eq!(config.volume, 40);
+
+        let newer = file.replacen(&format!("\"version\": {EXPORT_VERSION}"), "\"version\": 99", 1);
+        assert_eq!(ConfigExport::from_json(&newer), Err(ImportError::NewerVersion(99)));
+        assert_eq!(ConfigExport::from_json("{}"), Err(ImportError::Invalid));
+        let _ = std::fs::remove_file(outside);
+    }
+
+    #[test]
+    fn imported_command_and_webhook_rules_are_disabled() {
+        let shared = Config {
+            rules: vec![
+                Rule {
+                    action: RuleAction::Command("rm -rf ~".to_string()),
+                    ..Default::default()
+                },
+                Rule {
+                    action: RuleAction::Webhook("https://example.com/hook".to_string()),
+                    ..Default::default()
+                },
+                Rule {
+                    action: RuleAction::Toast("{name} is {to}".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let file = ConfigExport::new(&shared).to_json();
+
+        let mut config = Config::default();
+        let summary = ConfigExport::from_json(&file).unwrap().merge_into(&mut config, false, None);
+        assert_eq!((summary.added_rules, summary.disabled_rules), (3, 2));
+        assert!(config.rules.iter().all(|r| r.enabled == matches!(r.action, RuleAction::Toast(_))));
+
+        // Enabling an imported rule does not make it importable again
+        config.rules[0].enabled = true;
+        let summary = ConfigExport::from_json(&file).unwrap().merge_into(&mut config, false, None);
+        assert_eq!(summary.added_rules, 0);
+    }
+}
@@ -1,94 +1,97 @@
+use chrono::{NaiveDate, Utc};
 use eframe::{
     App, CreationContext,
     egui::{
         self, Align, Button, Color32, ComboBox, CursorIcon, DragValue,
         FontFamily::Proportional,
-        FontId, Frame, Id, Image, Layout, Margin, Modal, RichText, ScrollArea, Slider, TextEdit,
+        FontId, Frame, Id, Image, Layout, Margin, Modal, Rect, RichText, ScrollArea, Sense, Slider, TextEdit,
         TextStyle::{self, *},
-        Theme, Vec2,
-        containers::{CentralPanel, Tooltip, TopBottomPanel},
+        Theme, Vec2, ViewportCommand,
+        containers::{CentralPanel, Popup, PopupCloseBehavior, SidePanel, Tooltip, TopBottomPanel},
         text::{LayoutJob, TextFormat},
     },
 };
-use lolclientapi_rs::blocking::LeagueClient;
-use notify_rust::Notification;
-use serde::{Deserialize, Serialize};
-use std::sync::mpsc::{Receiver, Sender, channel};
-use std::thread;
+use std::sync::mpsc::Sender;
 use uuid::Uuid;
 
-use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex};
+use lolfriendsnotifier::APP_VERSION;
+use lolfriendsnotifier::assets;
+use lolfriendsnotifier::audio::{self, AudioState};
+use lolfriendsnotifier::config::{Config, Friend, FriendGroup, Sound};
+use lolfriendsnotifier::engine::{EngineHandle, Message};
+use lolfriendsnotifier::export::ConfigExport;
+use lolfriendsnotifier::history::{History, HistoryEntry};
+use lolfriendsnotifier::migrations;
+use lolfriendsnotifier::presence::{ApiFriend, FriendStatus};
+use lolfriendsnotifier::rules::{Rule, RuleAction, RuleTarget};
+use lolfriendsnotifier::service::{self, Service};
+use lolfriendsnotifier::stats::{self, FriendStats};
+
+use crate::tray::Tray;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
+use tracing::warn;
 
-// We derive Deserialize/Serialize so we can persist app state on shutdown.
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(default)] // if we add new fields, give them default values when deserializing old state
+/// View of the engine state, edits made through the widgets are sent back to the engine as a new configuration
 pub struct FriendsNotifierApp {
-    friends: Vec<Friend>,
-    native_notification: bool,
-    volume: u8,
-    notify_away_status: bool,
-    notify_in_game_status: bool,
-    dark_mode: bool,
-    #[serde(skip)]
-    g_sx: Sender<Message>,
-    #[serde(skip)]
-    g_rx: Receiver<Message>,
-    #[serde(skip)]
+    // Local copy of the configuration, replaced by the engine one once it applied every edit
+    config: Config,
+    // Incremented on every edit, the engine echoes the last revision it applied in its snapshots
+    config_revision: u64,
+    snapshot_version: u64,
+    engine: EngineHandle,
     s_sx: Sender<Message>,
-    #[serde(skip)]
+    audio: Arc<AudioState>,
     client_status: bool,
-    #[serde(skip)]
     settings_open: bool,
+    rules_open: bool,
+    demo: bool,
+    // Latest friends list received from the client, feeds the friend pickers
+    api_friends: Vec<ApiFriend>,
+    picker_open: bool,
+    picker_query: String,
+    picker_selected: Vec<String>,
+    sounds_open: bool,
+    // Imported sound files are copied in this directory, next to the persisted state
+    sounds_dir: Option<PathBuf>,
+    sound_import_path: String,
+    // Files that could not be imported, shown in the sounds modal
+    sound_import_errors: Vec<String>,
+    history: History,
+    history_open: bool,
+    // Loaded when the history modal or the stats panel is opened
+    history_entries: LoadedHistory,
+    history_friend: Option<Uuid>,
+    // Dates typed in the filters, as YYYY-MM-DD
+    history_from: String,
+    history_to: String,
+    // Stats panel next to the table, computed from the loaded history entries
+    stats_open: bool,
+    stats_friend: Option<Uuid>,
+    stats_export_path: String,
+    stats_export_result: Option<String>,
+    // Export file of the friends, rules, sounds and settings, shared between teammates or kept as a backup
+    transfer_path: String,
+    transfer_settings: bool,
+    transfer_result: Option<String>,
+    // Name typed in the profile switcher
+    profile_input: String,
+    // Deleting the active profile is asked for confirmation, its friends and rules cannot be recovered
+    profile_delete_open: bool,
+    // Profile given on the command line that does not exist, shown next to the switcher until another one is picked
+    unknown_profile: Option<String>,
+    // Closing the window hides it while there is a tray icon, the app only quits from the tray menu
+    tray: Option<Tray>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-struct Friend {
-    pub uuid: Uuid,
-    pub timer_id: Uuid,
-    pub name: String,
-    pub sound: Sound,
-    pub is_repeat: bool,
-    pub notify_timer: u16,
-    #[serde(skip)]
-    pub enabled: bool,
-    #[serde(skip)]
-    pub status: FriendStatus,
-}
-
-#[derive(Debug, Clone, Default, PartialEq)]
-enum FriendStatus {
-    Online,
-    InGame,
-    Mobile,
-    Away,
-    #[default]
-    Offline,
-}
-
-#[derive(Debug, Clone)]
-pub struct ApiFriend {
-    pub riot_id: String,
-    pub availability: String,
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Sound {
-    pub label: String,
-    pub path: String,
-}
-
-#[derive(Debug, Clone, Default)]
-pub enum Message {
-    ClientStatus(bool),
-    FriendStatus(Vec<ApiFriend>),
-    SpawnTimer(Friend),
-    Notify(Friend),
-    PlaySound(String),
-    SetVolume(u8),
-    #[default]
-    None,
+// Changes requested from a friend row or group header, applied once the table has been drawn
+enum RowAction {
+    Delete(Uuid),
+    MoveTo(Uuid, Option<Uuid>),
+    DeleteGroup(Uuid),
+    AddToGroup(Uuid),
 }
 
 // Redefine Global Fonts
@@ -102,7 +105,9 @@ const TEXT_STYLES: [(TextStyle, FontId); 5] = [
 
 impl FriendsNotifierApp {
     // Called once before the first frame to initialize gui configuration.
-    pub fn new(cc: &CreationContext<'_>) -> Self {
+    // In demo mode the persisted state is neither loaded nor saved and a scripted client replaces League.
+    // The given profile is made active when it exists.
+    pub fn new(cc: &CreationContext<'_>, demo: bool, profile: Option<String>) -> Self {
         egui_extras::install_image_loaders(&cc.egui_ctx);
 
         cc.egui_ctx.all_styles_mut(move |style| {
@@ -110,36 +115,113 @@ impl FriendsNotifierApp {
             style.interaction.selectable_labels = false;
         });
 
-        let mut app: FriendsNotifierApp;
-        // Load previous app state (if any).
-        if let Some(storage) = cc.storage {
-            app = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut config: Config;
+        // Load previous configuration (if any).
+        if demo {
+            config = Config::demo();
+        } else if let Some(blob) = cc.storage.and_then(|s| s.get_string(eframe::APP_KEY)) {
+            // Older layouts are migrated, the previous blob is kept next to the persisted state
+            config = migrations::load(&blob, eframe::storage_dir(crate::APP_ID).as_deref());
         } else {
-            app = FriendsNotifierApp::default();
+            config = Config::default();
+        }
+
+        let unknown_profile = profile.filter(|name| !config.switch_profile(name));
+        if let Some(name) = &unknown_profile {
+            warn!("Unknown profile '{name}', available: {}", config.profile_names().join(", "));
         }
 
-        app.dark_mode = match cc.egui_ctx.theme() {
+        config.dark_mode = match cc.egui_ctx.theme() {
             Theme::Dark => true,
             Theme::Light => false,
         };
 
-        let client = Arc::new(Mutex::new(LeagueClient::new()));
-        let (g_sx, g_rx) = channel::<Message>();
-        let (s_sx, s_rx) = channel::<Message>();
-        // Initialize client threads
-        crate::start_polling_league_client(g_sx.clone(), Some(client.clone()));
-        crate::start_audio_message_receiver(s_rx);
-
-        app.g_sx = g_sx;
-        app.g_rx = g_rx;
-        app.s_sx = s_sx;
-        app
+        // The engine and audio thread wake the gui up on every change, the gui is otherwise only repainted on user input
+        let ctx = cc.egui_ctx.clone();
+        let history = match demo {
+            true => History::default(),
+            false => History::new(eframe::storage_dir(crate::APP_ID).map(|dir| dir.join("history.jsonl"))),
+        };
+        let Service { engine, s_sx, audio } = service::start(config.clone(), history.clone(), demo, move || ctx.request_repaint());
+
+        let tray = Tray::new(&cc.egui_ctx, engine.clone(), demo);
+        Self {
+            config,
+            config_revision: 0,
+            snapshot_version: 0,
+            engine,
+            s_sx,
+            audio,
+            client_status: false,
+            settings_open: false,
+            rules_open: false,
+            demo,
+            api_friends: vec![],
+            picker_open: false,
+            picker_query: "".to_string(),
+            picker_selected: vec![],
+            sounds_open: false,
+            sounds_dir: eframe::storage_dir(crate::APP_ID).map(|dir| audio::sounds_dir(&dir)),
+            sound_import_path: "".to_string(),
+            sound_import_errors: vec![],
+            history,
+            history_open: false,
+            history_entries: LoadedHistory::default(),
+            history_friend: None,
+            history_from: "".to_string(),
+            history_to: "".to_string(),
+            stats_open: false,
+            stats_friend: None,
+            stats_export_path: eframe::storage_dir(crate::APP_ID)
+                .map(|dir| dir.join("stats.csv").display().to_string())
+                .unwrap_or_default(),
+            stats_export_result: None,
+            transfer_path: eframe::storage_dir(crate::APP_ID)
+                .map(|dir| dir.join("export.json").display().to_string())
+                .unwrap_or_default(),
+            transfer_settings: false,
+            transfer_result: None,
+            profile_input: "".to_string(),
+            profile_delete_open: false,
+            unknown_profile,
+            tray,
+        }
+    }
+
+    // Copies audio files into the managed sounds directory, they can then be picked like the built-in sounds
+    fn import_sounds(&mut self, files: Vec<PathBuf>) {
+        let Some(dir) = &self.sounds_dir else { return };
+        self.sound_import_errors.clear();
+        for file in files {
+            match audio::import_sound(&file, dir) {
+                Some(sound) => self.config.custom_sounds.push(sound),
+                None => self.sound_import_errors.push(file.display().to_string()),
+            }
+        }
+    }
+
+    // Takes the latest engine state, the local configuration is kept while the engine has not applied its edits yet
+    fn sync_snapshot(&mut self) {
+        if let Some(snapshot) = self.engine.snapshot_since(self.snapshot_version) {
+            self.snapshot_version = snapshot.version;
+            self.client_status = snapshot.client_status;
+            self.api_friends = snapshot.api_friends;
+            if snapshot.config_revision == self.config_revision {
+                self.config = snapshot.config;
+            }
+        }
     }
 }
 
 impl App for FriendsNotifierApp {
     fn save(&mut self, _storage: &mut dyn eframe::Storage) {
-        eframe::set_value(_storage, eframe::APP_KEY, &self);
+        if !self.demo {
+            eframe::set_value(_storage, eframe::APP_KEY, &self.config);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.engine.shutdown();
     }
 
     fn auto_save_interval(&self) -> Duration {
@@ -147,111 +229,34 @@ impl App for FriendsNotifierApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
-        // Force egui to repaint every 16ms (60 FPS), avoids 'pausing' the application
-        // background threads are also paused if a repaint is not esplicitly requested
-        // meaning notifying is out of sync with actual specified Friends timers and only
-        // trigger on user interaction with the gui, there might be performance implications
-        // by running it every loop, TODO call ctx.request_repaint when needed from the other threads
-        ctx.request_repaint();
-
-        // Handle messages to mutate state before initializing widgets
-        let msg = self.g_rx.try_recv().unwrap_or_default();
-        match msg {
-            // Update gui friend status, and send notification if is found in an active state
-            Message::FriendStatus(fr) => {
-                for f in self.friends.iter_mut() {
-                    if let Some(api_friend) = fr.iter().find(|_f| _f.riot_id == f.name.to_lowercase()) {
-                        let new_status: FriendStatus = api_friend.into();
-                        let old_status = f.status.clone(); // Clone old status for comparison before mutation.
-
-                        // Determine if a notification should be sent based on several conditions.
-                        let should_notify = {
-                            // Helper closure to determine if a status is considered "active" based on settings.
-                            // This now includes checking for the new `notify_in_game_status` flag.
-                            let is_status_active = |status: &FriendStatus| -> bool {
-                                matches!(status, FriendStatus::Online)
-                                    || (self.notify_away_status && matches!(status, FriendStatus::Away))
-                                    || (self.notify_in_game_status && matches!(status, FriendStatus::InGame))
-                            };
-
-                            let was_active = is_status_active(&old_status);
-                            let is_now_active = is_status_active(&new_status);
-
-                            // A meaningful change happens when the friend transitions between active and non-active states.
-                            let is_meaningful_change = was_active != is_now_active;
-
-                            // Explicitly ignore notifications for becoming "Away" or "InGame" if their respective settings are off,
-                            // even if it's considered a meaningful change (e.g., Online -> Away).
-                            let is_ignored_transition = (!self.notify_away_status && matches!(new_status, FriendStatus::Away))
-                                || (!self.notify_in_game_status && matches!(new_status, FriendStatus::InGame));
-
-                            f.enabled && is_meaningful_change && !is_ignored_transition
-                        };
-
-                        // Always update the friend's status to reflect the latest data.
-                        f.status = new_status;
-                        if should_notify {
-                            let _ = self.g_sx.send(Message::Notify(f.clone()));
-                        }
-                    } else {
-                        // For friends not found in the API response set them to Offline.
-                        f.status = FriendStatus::Offline;
-                    }
-                }
-            }
-            Message::ClientStatus(status) => {
-                // Set all friends statuses to Offline, this is needed in case the client is closed after retrieving some statuses
-                if !status {
-                    for f in self.friends.iter_mut() {
-                        f.status = FriendStatus::Offline
-                    }
-                }
-
-                self.client_status = status;
-            }
-            // Spawn a timer thread when a friend is enabled, at timeout try to send a notification
-            Message::SpawnTimer(f) => {
-                let g_sx = self.g_sx.clone();
-                thread::spawn(move || {
-                    thread::sleep(Duration::from_secs(f.notify_timer as u64));
-                    let _ = g_sx.send(Message::Notify(f));
-                });
-            }
-            // When timer is triggered we check if conditions changed while waiting for the timer
-            Message::Notify(fr) => {
-                if let Some(friend) = self.friends.iter().find(|f| f == &&fr) {
-                    // Friend must be online and timer_id matches
-                    // Timer_id mismatch happens because the background thread that triggers after
-                    // the defined timer times out has no idea whether this was the the original call to
-                    // spawn it or a different one for the same friend (each time a friend is enabled it will spawn a timer)
-                    // and since timer_id is regenerated everytime a friend is enabled we make sure it was the
-                    // last call to spawn it by comparing the timer id
-                    match (&friend.status, self.notify_away_status, self.notify_in_game_status) {
-                        (FriendStatus::Online, _, _) | (FriendStatus::Away, true, _) | (FriendStatus::InGame, _, true) => {
-                            if friend.timer_id == fr.timer_id {
-                                // Handle repeating the notification
-                                if friend.is_repeat {
-                                    let _ = self.g_sx.send(Message::SpawnTimer(friend.clone()));
-                                }
-                                // Now that conditions are met, play the sound associated with this Friend
-                                let _ = self.s_sx.send(Message::PlaySound(friend.sound.path.clone()));
-                                // Send the windows notification if enabled
-                                if self.native_notification {
-                                    let _ = Notification::new()
-                                        .appname("Friends Notifier")
-                                        .timeout(Duration::from_millis(5000))
-                                        .body(&format!("{} is Online!", fr.name))
-                                        .auto_icon()
-                                        .finalize()
-                                        .show();
-                                };
-                            }
-                        }
-                        _ => {}
-                    }
-                };
-            }
-            _ => {}
+        // Take the engine state before initializing widgets, edits made this frame are compared against it
+        self.sync_snapshot();
+        let previous_config = self.config.clone();
+        // Reminder countdowns are not pushed by the engine, refresh them every second
+        if self.config.friends.iter().any(|f| f.enabled && self.engine.reminder_remaining(f.uuid).is_some()) {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        if let Some(tray) = &self.tray {
+            tray.update(&self.config, self.client_status);
+        }
+        // Entries recorded by the engine while the stats are shown are taken into account
+        if self.stats_open && self.history.revision() != self.history_entries.revision {
+            self.history_entries.load(&self.history);
+        }
+        // Audio files dropped on the window are imported as sounds
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect());
+        if !dropped.is_empty() {
+            self.import_sounds(dropped);
+            self.sounds_open = true;
+        }
+        let sounds = self.config.sounds();
+        let sounds = SoundChoices {
+            sounds: &sounds,
+            s_sx: &self.s_sx,
+        };
+        if ctx.input(|i| i.viewport().close_requested()) && self.tray.is_some() {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(ViewportCommand::Visible(false));
         }
 
         // Apply tint to loaded images to match theme
@@ -283,6 +288,57 @@ impl App for FriendsNotifierApp {
                     if ui.add(settings_btn).clicked() {
                         self.settings_open = !self.settings_open;
                     };
+                    // Switching profiles is an edit like any other, the engine gets the other friends, rules and flags
+                    let mut switch_to = None;
+                    ComboBox::from_id_salt("profile").width(90.0).selected_text(self.config.profile.clone()).show_ui(ui, |ui| {
+                        for name in self.config.profile_names() {
+                            if ui.selectable_label(name == self.config.profile, &name).clicked() {
+                                switch_to = Some(name);
+                            }
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(TextEdit::singleline(&mut self.profile_input).desired_width(80.0).hint_text("New profile"));
+                            if ui.button("Add").clicked() && self.config.add_profile(self.profile_input.trim()) {
+                                self.profile_input.clear();
+                            }
+                        });
+                        if ui
+                            .add_enabled(!self.config.profiles.is_empty(), Button::new("Delete this profile"))
+                            .on_hover_text("Its friends and rules are deleted along with it")
+                            .clicked()
+                        {
+                            self.profile_delete_open = true;
+                        }
+                    });
+                    if let Some(name) = &self.unknown_profile {
+                        let text = format!("Unknown profile '{name}', started with '{}'", self.config.profile);
+                        ui.label(RichText::from("⚠").color(ui.visuals().warn_fg_color)).on_hover_text(text);
+                    }
+                    if let Some(name) = switch_to {
+                        self.config.switch_profile(&name);
+                        self.unknown_profile = None;
+                    }
+                    if ui.add_enabled(!self.api_friends.is_empty(), Button::new("Friends List")).clicked() {
+                        self.picker_open = true;
+                        self.picker_query.clear();
+                        self.picker_selected.clear();
+                    };
+                    if ui.button("Rules").clicked() {
+                        self.rules_open = true;
+                    };
+                    if ui.button("Sounds").clicked() {
+                        self.sounds_open = true;
+                        self.sound_import_errors.clear();
+                    };
+                    if ui.button("History").clicked() {
+                        self.history_open = true;
+                        self.history_entries.load(&self.history);
+                    };
+                    if ui.selectable_label(self.stats_open, "Stats").clicked() {
+                        self.stats_open = !self.stats_open;
+                        self.history_entries.load(&self.history);
+                    };
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                         ui.style_mut().spacing.item_spacing = [5.0, 0.0].into();
                         ui.add(match self.client_status {
@@ -290,11 +346,89 @@ impl App for FriendsNotifierApp {
                             false => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_RED),
                         });
                         ui.label(RichText::from("Client").italics().size(11.0));
+                        ui.add_space(5.0);
+                        let device = self.audio.device.lock().unwrap().clone();
+                        let audio_status = ui.add(match device.is_some() {
+                            true => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_GREEN),
+                            false => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_RED),
+                        });
+                        ui.label(RichText::from("Audio").italics().size(11.0));
+                        match device {
+                            Some(name) => audio_status.on_hover_text(format!("Playing sounds on {name}")),
+                            None => audio_status.on_hover_text("No audio device available, sounds are not played"),
+                        };
                     })
                 });
             });
         });
 
+        // Stats of a tracked friend, drawn before the central panel so the table keeps the remaining width
+        if self.stats_open {
+            SidePanel::right("stats_panel").resizable(false).exact_width(200.0).show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Stats").on_hover_cursor(CursorIcon::Default);
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.button("Refresh").clicked() {
+                            self.history_entries.load(&self.history);
+                        };
+                    })
+                });
+                let friends: Vec<&Friend> = self.config.friends.iter().filter(|f| !f.name.is_empty()).collect();
+                if !friends.iter().any(|f| Some(f.uuid) == self.stats_friend) {
+                    self.stats_friend = friends.first().map(|f| f.uuid);
+                }
+                let Some(uuid) = self.stats_friend else {
+                    ui.label(RichText::from("No tracked friends").italics());
+                    return;
+                };
+                let selected = friends.iter().find(|f| f.uuid == uuid).map_or("", |f| &f.name);
+                ComboBox::from_id_salt("stats_friend")
+                    .width(ui.available_width())
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for f in &friends {
+                            ui.selectable_value(&mut self.stats_friend, Some(f.uuid), &f.name);
+                        }
+                    });
+                ui.separator();
+                let now = Utc::now().timestamp();
+                let friend_stats = self.history_entries.stats(uuid, now);
+                let average = friend_stats.average_session.as_secs();
+                ui.label(format!("{} sessions, {}h{:02} on average", friend_stats.sessions, average / 3600, average % 3600 / 60));
+                ui.add_space(3.0);
+                // Last days first
+                for (date, day) in friend_stats.days.iter().rev().take(7) {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::from(date.format("%a %d/%m").to_string()).weak());
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.label(format!("{:.1}h online, {:.1}h in game", day.online_hours(), day.in_game_hours()));
+                        })
+                    });
+                }
+                ui.add_space(5.0);
+                ui.label(RichText::from("Logins by weekday and hour").size(8.0));
+                login_heatmap(ui, friend_stats);
+                ui.separator();
+                // Every tracked friend is exported, one line per day
+                ui.horizontal(|ui| {
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.add_enabled(!self.stats_export_path.trim().is_empty(), Button::new("Export CSV")).clicked() {
+                            let all: Vec<FriendStats> = friends.iter().map(|f| stats::compute(&self.history_entries.entries, f.uuid, now)).collect();
+                            let path = self.stats_export_path.trim();
+                            self.stats_export_result = Some(match std::fs::write(path, stats::to_csv(&all)) {
+                                Ok(_) => format!("Exported to {path}"),
+                                Err(e) => format!("Could not export: {e}"),
+                            });
+                        }
+                        ui.add_sized(ui.available_size(), TextEdit::singleline(&mut self.stats_export_path).hint_text("Path of the csv file"));
+                    })
+                });
+                if let Some(result) = &self.stats_export_result {
+                    ui.label(RichText::from(result).size(8.0));
+                }
+            });
+        }
+
         // Main central layouts and widgets of the app
         CentralPanel::default()
             .frame(
@@ -317,7 +451,7 @@ impl App for FriendsNotifierApp {
                     ui.label("Name#Tag").on_hover_cursor(CursorIcon::Default);
                     ui.add_space(80.0);
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                        ui.add_space(70.0);
+                        ui.add_space(96.0);
                         ui.label("Repeat").on_hover_cursor(CursorIcon::Default);
                         ui.add_space(82.0);
                         ui.label("Sound").on_hover_cursor(CursorIcon::Default);
@@ -338,14 +472,13 @@ impl App for FriendsNotifierApp {
                                     bottom: 2,
                                 })
                                 .show(ui, |ui| {
-                                    // Add/Remove rows buttons are added
+                                    // Add row and group buttons are added
                                     ui.style_mut().spacing.item_spacing = [5.0, 0.0].into();
-                                    if ui.add(Button::image(icon_dash)).clicked() && self.friends.len() > crate::ALLOWED_MIN_FRIENDS {
-                                        let _f = self.friends.pop().unwrap();
+                                    if ui.add(Button::image_and_text(icon_plus.clone(), "Group")).clicked() {
+                                        self.config.groups.push(FriendGroup::default());
                                     };
-                                    if ui.add(Button::image(icon_plus)).clicked() && self.friends.len() < crate::ALLOWED_MAX_FRIENDS {
-                                        let _f = Friend::default();
-                                        self.friends.push(_f.clone());
+                                    if ui.add(Button::image(icon_plus.clone())).clicked() {
+                                        self.config.friends.push(Friend::default());
                                     };
                                 });
                         });
@@ -353,92 +486,316 @@ impl App for FriendsNotifierApp {
                     ui.with_layout(Layout::top_down(Align::Center), |ui| {
                         // Main scroll area of this app where friend rows will be added
                         // Has to be the last nested child so it can take as much space left within the main window
+                        // Ungrouped friends come first, followed by each group and its friends
+                        let mut actions: Vec<RowAction> = vec![];
                         ScrollArea::vertical().show(ui, |ui| {
-                            for (i, friend) in self.friends.iter_mut().enumerate() {
-                                ui.horizontal(|ui| {
-                                    ui.style_mut().spacing.item_spacing = [2.0, 0.0].into();
-                                    // Friend notification enabling button widget
-                                    if ui.add(Button::selectable(friend.enabled, icon_check.clone()).frame_when_inactive(true)).clicked() {
-                                        friend.enabled = !friend.enabled;
-                                        friend.timer_id = Uuid::new_v4();
-                                        if friend.enabled {
-                                            let _ = self.g_sx.send(Message::Notify(friend.clone()));
-                                        };
-                                    };
-                                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                        ui.style_mut().spacing.button_padding = [10.0, 0.0].into();
-                                        // Friend status icon widget
-                                        let friend_status_img = match friend.status {
-                                            FriendStatus::Online => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_GREEN),
-                                            FriendStatus::InGame => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_CYAN),
-                                            FriendStatus::Mobile => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_GREY),
-                                            FriendStatus::Away => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_YELLOW),
-                                            FriendStatus::Offline => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_RED),
-                                        };
-                                        let friend_status_img_res = ui.add(friend_status_img);
-                                        Tooltip::for_enabled(&friend_status_img_res).show(|ui| match friend.status {
-                                            FriendStatus::Online => ui.label("Online"),
-                                            FriendStatus::InGame => ui.label("In Game"),
-                                            FriendStatus::Mobile => ui.label("Mobile"),
-                                            FriendStatus::Away => ui.label("Away"),
-                                            FriendStatus::Offline => ui.label("Offline"),
-                                        });
-
-                                        ui.separator();
-                                        // Repeat notification button and value widgets
-                                        ui.add_enabled(
-                                            !friend.enabled,
-                                            DragValue::new(&mut friend.notify_timer).range(5..=100).suffix("s").update_while_editing(false),
-                                        );
-                                        if ui.add(Button::selectable(friend.is_repeat, icon_repeat.clone()).frame_when_inactive(true)).clicked() {
-                                            friend.is_repeat = !friend.is_repeat;
-                                            if friend.is_repeat {
-                                                friend.timer_id = Uuid::new_v4();
-                                                let _ = self.g_sx.send(Message::Notify(friend.clone()));
-                                            }
-                                        };
-                                        ui.separator();
-                                        // Friend specific sound, combobox selector widget
-                                        let before = &friend.sound.label.clone();
-                                        ComboBox::from_id_salt(format!("box{i}")).selected_text(&friend.sound.label).show_ui(ui, |ui| {
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[0].0.to_string(), crate::ASSET_SOUNDS[0].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[1].0.to_string(), crate::ASSET_SOUNDS[1].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[2].0.to_string(), crate::ASSET_SOUNDS[2].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[3].0.to_string(), crate::ASSET_SOUNDS[3].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[4].0.to_string(), crate::ASSET_SOUNDS[4].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[5].0.to_string(), crate::ASSET_SOUNDS[5].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[6].0.to_string(), crate::ASSET_SOUNDS[6].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[7].0.to_string(), crate::ASSET_SOUNDS[7].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[8].0.to_string(), crate::ASSET_SOUNDS[8].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[9].0.to_string(), crate::ASSET_SOUNDS[9].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[10].0.to_string(), crate::ASSET_SOUNDS[10].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[11].0.to_string(), crate::ASSET_SOUNDS[11].0);
-                                            ui.selectable_value(&mut friend.sound.label, crate::ASSET_SOUNDS[12].0.to_string(), crate::ASSET_SOUNDS[12].0);
-                                        });
-                                        // Handle changing the underlying sound played when notifying for this friend
-                                        if &friend.sound.label != before {
-                                            if let Some(s) = crate::ASSET_SOUNDS.iter().find(|_s| _s.0 == friend.sound.label) {
-                                                friend.sound = Sound {
-                                                    label: s.0.to_string(),
-                                                    path: s.1.to_string(),
-                                                };
-                                                let _ = self.s_sx.send(Message::PlaySound(friend.sound.path.clone()));
-                                            }
+                            let sections: Vec<Option<Uuid>> = std::iter::once(None).chain(self.config.groups.iter().map(|g| Some(g.uuid))).collect();
+                            for section in sections {
+                                if let Some(group) = self.config.groups.iter_mut().find(|g| Some(g.uuid) == section) {
+                                    let previous_sound = group.sound.clone();
+                                    actions.extend(group_header(ui, group, &icon_check, &icon_plus, &icon_dash, &sounds));
+                                    // Members still using the previous default sound follow the group
+                                    if group.sound.path != previous_sound.path {
+                                        for f in self.config.friends.iter_mut().filter(|f| f.group == section && f.sound.path == previous_sound.path) {
+                                            f.sound = group.sound.clone();
                                         }
-                                        ui.separator();
-                                        // Finally the friend name box widget
-                                        ui.add_sized(
-                                            ui.available_size(),
-                                            TextEdit::singleline(&mut friend.name).vertical_align(Align::Center).interactive(!friend.enabled),
-                                        )
-                                    })
-                                });
-                                ui.separator();
+                                    }
+                                    ui.separator();
+                                }
+                                for friend in self.config.friends.iter_mut().filter(|f| f.group == section) {
+                                    let row = RowContext {
+                                        api_friends: &self.api_friends,
+                                        groups: &self.config.groups,
+                                        sounds: &sounds,
+                                        engine: &self.engine,
+                                        icon_check: &icon_check,
+                                        icon_repeat: &icon_repeat,
+                                        icon_dash: &icon_dash,
+                                    };
+                                    actions.extend(friend_row(ui, friend, &row));
+                                    ui.separator();
+                                }
                             }
                         });
+                        for action in actions {
+                            match action {
+                                RowAction::Delete(uuid) => self.config.friends.retain(|f| f.uuid != uuid),
+                                RowAction::MoveTo(uuid, group) => {
+                                    if let Some(f) = self.config.friends.iter_mut().find(|f| f.uuid == uuid) {
+                                        f.group = group;
+                                    }
+                                }
+                                // Friends of a deleted group are kept ungrouped
+                                RowAction::DeleteGroup(uuid) => {
+                                    self.config.groups.retain(|g| g.uuid != uuid);
+                                    for f in self.config.friends.iter_mut().filter(|f| f.group == Some(uuid)) {
+                                        f.group = None;
+                                    }
+                                }
+                                RowAction::AddToGroup(uuid) => {
+                                    let friend = self.config.new_friend(Some(uuid));
+                                    self.config.friends.push(friend);
+                                }
+                            }
+                        }
                     });
                 });
             });
+        // Friends list modal, allows picking several friends from the client at once
+        if self.picker_open
+            && Modal::new(Id::new("picker_modal"))
+                .show(ctx, |ui| {
+                    ui.set_width(240.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("Friends List").on_hover_cursor(CursorIcon::Default);
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.add_space(10.0);
+                            if ui.add(Button::new("X").frame(false)).clicked() {
+                                self.picker_open = false;
+                            };
+                        })
+                    });
+                    ui.separator();
+                    ui.add(TextEdit::singleline(&mut self.picker_query).hint_text("Search"));
+                    ui.add_space(3.0);
+                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for api_friend in search_friends(&self.api_friends, &self.picker_query) {
+                            let is_tracked = self.config.friends.iter().any(|f| f.matches(api_friend));
+                            let mut is_selected = self.picker_selected.contains(&api_friend.puuid);
+                            ui.horizontal(|ui| {
+                                ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+                                if ui.add_enabled(!is_tracked, egui::Checkbox::without_text(&mut is_selected)).changed() {
+                                    match is_selected {
+                                        true => self.picker_selected.push(api_friend.puuid.clone()),
+                                        false => self.picker_selected.retain(|p| p != &api_friend.puuid),
+                                    }
+                                }
+                                ui.add(status_icon(api_friend.into()));
+                                ui.label(&api_friend.riot_id);
+                                if is_tracked {
+                                    ui.label(RichText::from("tracked").italics().size(8.0));
+                                }
+                            });
+                        }
+                    });
+                    // Friend groups defined in the client can be imported with all of their members
+                    let mut client_groups: Vec<&str> = self
+                        .api_friends
+                        .iter()
+                        .map(|f| f.group_name.as_str())
+                        .filter(|g| !g.is_empty() && *g != "**Default")
+                        .collect();
+                    client_groups.sort();
+                    client_groups.dedup();
+                    let mut import_group = None;
+                    if !client_groups.is_empty() {
+                        ui.separator();
+                        ui.label(RichText::from("Client Groups").italics());
+                        for group_name in client_groups {
+                            ui.horizontal(|ui| {
+                                let members = self.api_friends.iter().filter(|f| f.group_name == group_name).count();
+                                ui.label(format!("{group_name} ({members})"));
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    if ui.button("Import").clicked() {
+                                        import_group = Some(group_name.to_string());
+                                    }
+                                });
+                            });
+                        }
+                    }
+                    if let Some(group_name) = import_group {
+                        self.config.import_client_group(&group_name, &self.api_friends);
+                    }
+                    ui.separator();
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        let add_btn = Button::new(format!("Add {} selected", self.picker_selected.len()));
+                        if ui.add_enabled(!self.picker_selected.is_empty(), add_btn).clicked() {
+                            for puuid in std::mem::take(&mut self.picker_selected) {
+                                if let Some(api_friend) = self.api_friends.iter().find(|f| f.puuid == puuid) {
+                                    self.config.add_friend(api_friend, None);
+                                }
+                            }
+                            self.picker_open = false;
+                        };
+                    })
+                })
+                .should_close()
+        {
+            self.picker_open = false;
+        };
+        // Rules modal, each rule runs its action when a friend status changes the way it describes
+        if self.rules_open
+            && Modal::new(Id::new("rules_modal"))
+                .show(ctx, |ui| {
+                    ui.set_width(400.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("Rules").on_hover_cursor(CursorIcon::Default);
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.add_space(10.0);
+                            if ui.add(Button::new("X").frame(false)).clicked() {
+                                self.rules_open = false;
+                            };
+                        })
+                    });
+                    ui.separator();
+                    let mut deleted = None;
+                    ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                        for rule in self.config.rules.iter_mut() {
+                            if rule_row(ui, rule, &self.config.friends, &self.config.groups, &icon_check, &icon_dash, &sounds) {
+                                deleted = Some(rule.uuid);
+                            }
+                            ui.separator();
+                        }
+                    });
+                    if let Some(uuid) = deleted {
+                        self.config.rules.retain(|r| r.uuid != uuid);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::from("{name}, {from} and {to} are replaced in toasts").italics().size(8.0));
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            if ui.add(Button::image(icon_plus.clone())).on_hover_text("Add rule").clicked() {
+                                self.config.rules.push(Rule::default());
+                            };
+                        })
+                    });
+                })
+                .should_close()
+        {
+            self.rules_open = false;
+        };
+        // Sounds modal, imported sound files can be renamed, previewed and deleted
+        if self.sounds_open
+            && Modal::new(Id::new("sounds_modal"))
+                .show(ctx, |ui| {
+                    ui.set_width(300.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("Sounds").on_hover_cursor(CursorIcon::Default);
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.add_space(10.0);
+                            if ui.add(Button::new("X").frame(false)).clicked() {
+                                self.sounds_open = false;
+                            };
+                        })
+                    });
+                    ui.separator();
+                    let mut deleted = None;
+                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for sound in self.config.custom_sounds.iter_mut() {
+                            ui.horizontal(|ui| {
+                                ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+                                if ui.button("Play").clicked() {
+                                    let _ = self.s_sx.send(Message::PlaySound(sound.path.clone(), 100));
+                                }
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ui.style_mut().spacing.button_padding = [10.0, 0.0].into();
+                                    if ui.add(Button::image(icon_dash.clone())).on_hover_text("Delete sound").clicked() {
+                                        deleted = Some(sound.path.clone());
+                                    }
+                                    ui.add_sized(ui.available_size(), TextEdit::singleline(&mut sound.label).vertical_align(Align::Center));
+                                });
+                            });
+                            ui.separator();
+                        }
+                    });
+                    // Friends, groups and rules using a deleted sound go back to the default one
+                    // Only the copies made on import are removed from the disk
+                    if let Some(path) = deleted {
+                        self.config.remove_sound(&path);
+                        if self.sounds_dir.as_ref().is_some_and(|dir| audio::is_managed_sound(&path, dir)) {
+                            let _ = std::fs::remove_file(path);
+                        }
+                    }
+                    let mut import = false;
+                    ui.horizontal(|ui| {
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            import = ui.add_enabled(!self.sound_import_path.trim().is_empty(), Button::new("Import")).clicked();
+                            ui.add_sized(
+                                ui.available_size(),
+                                TextEdit::singleline(&mut self.sound_import_path).hint_text("Path to a wav, ogg, mp3 or flac file"),
+                            );
+                        })
+                    });
+                    if import {
+                        self.import_sounds(vec![PathBuf::from(self.sound_import_path.trim())]);
+                        self.sound_import_path.clear();
+                    }
+                    for file in &self.sound_import_errors {
+                        ui.label(RichText::from(format!("Could not import {file}")).color(Color32::from_rgb(230, 60, 60)).size(8.0));
+                    }
+                    ui.label(RichText::from("Audio files dropped on the window are imported too").italics().size(8.0));
+                    let assets = match assets::assets_dir() {
+                        Some(dir) => format!("Built-in sounds loaded from {}", dir.display()),
+                        None => format!("Built-in sound files not found, playing embedded copies. Set {} to their folder", assets::ASSETS_DIR_ENV),
+                    };
+                    ui.label(RichText::from(assets).italics().size(8.0));
+                })
+                .should_close()
+        {
+            self.sounds_open = false;
+        };
+        // History modal, entries are listed newest first and filtered by friend and date
+        if self.history_open
+            && Modal::new(Id::new("history_modal"))
+                .show(ctx, |ui| {
+                    ui.set_width(380.0);
+                    ui.horizontal(|ui| {
+                        ui.heading("History").on_hover_cursor(CursorIcon::Default);
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            ui.add_space(10.0);
+                            if ui.add(Button::new("X").frame(false)).clicked() {
+                                self.history_open = false;
+                            };
+                            if ui.button("Refresh").clicked() {
+                                self.history_entries.load(&self.history);
+                            };
+                        })
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+                        // Friends are listed by their last recorded name, removed ones included
+                        let mut friends: Vec<(Uuid, &str)> = vec![];
+                        for entry in self.history_entries.entries.iter().rev() {
+                            if !friends.iter().any(|(uuid, _)| *uuid == entry.friend) {
+                                friends.push((entry.friend, &entry.name));
+                            }
+                        }
+                        friends.sort_by_key(|(_, name)| name.to_lowercase());
+                        let selected = friends.iter().find(|(uuid, _)| Some(*uuid) == self.history_friend).map_or("Everyone", |(_, name)| name);
+                        ComboBox::from_id_salt("history_friend").width(120.0).selected_text(selected).show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.history_friend, None, "Everyone");
+                            for (uuid, name) in &friends {
+                                ui.selectable_value(&mut self.history_friend, Some(*uuid), *name);
+                            }
+                        });
+                        ui.label("From");
+                        ui.add(TextEdit::singleline(&mut self.history_from).desired_width(70.0).hint_text("YYYY-MM-DD"));
+                        ui.label("To");
+                        ui.add(TextEdit::singleline(&mut self.history_to).desired_width(70.0).hint_text("YYYY-MM-DD"));
+                    });
+                    ui.separator();
+                    // Dates that cannot be parsed leave the filter open
+                    let from = NaiveDate::parse_from_str(self.history_from.trim(), "%Y-%m-%d").ok();
+                    let to = NaiveDate::parse_from_str(self.history_to.trim(), "%Y-%m-%d").ok();
+                    let entries: Vec<&HistoryEntry> = self.history_entries.entries.iter().rev().filter(|e| e.matches(self.history_friend, from, to)).collect();
+                    ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                        for entry in &entries {
+                            ui.horizontal(|ui| {
+                                let time = entry.local_time().map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
+                                ui.label(RichText::from(time).weak());
+                                ui.label(&entry.name);
+                                ui.label(entry.event.describe());
+                            });
+                        }
+                        if entries.is_empty() {
+                            ui.label(RichText::from("Nothing recorded").italics());
+                        }
+                    });
+                })
+                .should_close()
+        {
+            self.history_open = false;
+        };
         // Settings modal, only drawn when it is supposed to be open
         if self.settings_open
             && Modal::new(Id::new("settings_modal"))
@@ -469,12 +826,12 @@ impl App for FriendsNotifierApp {
                                 ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
                                 ui.label("Theme");
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                    if ui.selectable_label(self.dark_mode, "Dark").clicked() {
-                                        self.dark_mode = true;
+                                    if ui.selectable_label(self.config.dark_mode, "Dark").clicked() {
+                                        self.config.dark_mode = true;
                                         ctx.set_theme(Theme::Dark);
                                     };
-                                    if ui.selectable_label(!self.dark_mode, "Light").clicked() {
-                                        self.dark_mode = false;
+                                    if ui.selectable_label(!self.config.dark_mode, "Light").clicked() {
+                                        self.config.dark_mode = false;
                                         ctx.set_theme(Theme::Light);
                                     };
                                 });
@@ -483,18 +840,35 @@ impl App for FriendsNotifierApp {
                             ui.horizontal(|ui| {
                                 ui.label("Volume").on_hover_cursor(CursorIcon::Default);
 
+                                // Applied by the engine along with the rest of the configuration
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                    if ui
-                                        .add_sized(
-                                            ui.available_size(),
-                                            Slider::new(&mut self.volume, 0..=100)
-                                                .handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.2 })
-                                                .show_value(false),
-                                        )
-                                        .drag_stopped()
-                                    {
-                                        let _ = self.s_sx.send(Message::SetVolume(self.volume));
-                                    };
+                                    ui.add_sized(
+                                        ui.available_size(),
+                                        Slider::new(&mut self.config.volume, 0..=100)
+                                            .handle_shape(egui::style::HandleShape::Rect { aspect_ratio: 1.2 })
+                                            .show_value(false),
+                                    );
+                                })
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Output").on_hover_cursor(CursorIcon::Default);
+                                // Falls back to the default device while the chosen one is unavailable
+                                let devices = self.audio.devices.lock().unwrap().clone();
+                                let selected = match &self.config.output_device {
+                                    Some(name) if devices.contains(name) => name.clone(),
+                                    Some(name) => format!("{name} (unavailable)"),
+                                    None => "Default".to_string(),
+                                };
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ComboBox::from_id_salt("output_device")
+                                        .width(ui.available_width())
+                                        .selected_text(selected)
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(&mut self.config.output_device, None, "Default");
+                                            for name in devices {
+                                                ui.selectable_value(&mut self.config.output_device, Some(name.clone()), name);
+                                            }
+                                        });
                                 })
                             });
                             ui.separator();
@@ -502,7 +876,7 @@ impl App for FriendsNotifierApp {
                                 ui.label("Windows Notification");
 
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                    ui.checkbox(&mut self.native_notification, "");
+                                    ui.checkbox(&mut self.config.native_notification, "");
                                 })
                             });
                             ui.horizontal(|ui| {
@@ -537,7 +911,7 @@ impl App for FriendsNotifierApp {
                                 ui.label(text);
 
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                    ui.checkbox(&mut self.notify_in_game_status, "");
+                                    ui.checkbox(&mut self.config.notify_in_game_status, "");
                                 })
                             });
                             ui.horizontal(|ui| {
@@ -571,76 +945,439 @@ impl App for FriendsNotifierApp {
                                 );
                                 ui.label(text);
                                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                                    ui.checkbox(&mut self.notify_away_status, "");
+                                    ui.checkbox(&mut self.config.notify_away_status, "");
+                                })
+                            });
+                            ui.separator();
+                            // Status debouncing, changes shorter than the dwell time are ignored
+                            ui.horizontal(|ui| {
+                                ui.label("Status dwell time").on_hover_text("How long a new status has to last before it is accepted");
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ui.add(DragValue::new(&mut self.config.status_dwell_secs).range(0..=120).suffix("s"));
+                                })
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Notification cooldown")
+                                    .on_hover_text("Status changes right after a notification of the same friend are not notified");
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ui.add(DragValue::new(&mut self.config.notify_cooldown_secs).range(0..=600).suffix("s"));
+                                })
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Offline after missed polls")
+                                    .on_hover_text("Friends missing from a single friends list are not set Offline right away");
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ui.add(DragValue::new(&mut self.config.missed_polls).range(1..=10));
+                                })
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("History retention").on_hover_text("Days the history is kept for, 0 keeps it forever");
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    ui.add(DragValue::new(&mut self.config.history_retention_days).range(0..=3650).suffix(" days"));
+                                })
+                            });
+                            ui.separator();
+                            // Imports are merged, friends already tracked and their rules are kept as they are
+                            ui.label("Friends, rules and settings file");
+                            ui.add(TextEdit::singleline(&mut self.transfer_path).hint_text("Path of the json file"));
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut self.transfer_settings, "").on_hover_text("Also apply the settings of the imported file");
+                                ui.label("Import settings");
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    let path = self.transfer_path.trim().to_string();
+                                    if ui.add_enabled(!path.is_empty(), Button::new("Import")).clicked() {
+                                        self.transfer_result = Some(match std::fs::read_to_string(&path) {
+                                            Ok(s) => match ConfigExport::from_json(&s) {
+                                                Ok(export) => export.merge_into(&mut self.config, self.transfer_settings, self.sounds_dir.as_deref()).label(),
+                                                Err(e) => e.label(),
+                                            },
+                                            Err(e) => format!("Could not read {path}: {e}"),
+                                        });
+                                    }
+                                    if ui.add_enabled(!path.is_empty(), Button::new("Export")).clicked() {
+                                        self.transfer_result = Some(match std::fs::write(&path, ConfigExport::new(&self.config).to_json()) {
+                                            Ok(_) => format!("Exported to {path}"),
+                                            Err(e) => format!("Could not export: {e}"),
+                                        });
+                                    }
                                 })
                             });
+                            if let Some(result) = &self.transfer_result {
+                                ui.label(RichText::from(result).size(8.0));
+                            }
                         });
                     ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                        ui.label(RichText::from("v".to_string() + crate::APP_VERSION).size(8.0));
+                        ui.label(RichText::from("v".to_string() + APP_VERSION).size(8.0));
                     })
                 })
                 .should_close()
         {
             self.settings_open = false;
         };
+        // Confirmation of the profile deletion, the next stored profile becomes the active one
+        if self.profile_delete_open
+            && Modal::new(Id::new("profile_delete_modal"))
+                .show(ctx, |ui| {
+                    ui.set_width(220.0);
+                    ui.heading("Delete profile").on_hover_cursor(CursorIcon::Default);
+                    ui.separator();
+                    ui.label(format!("Delete '{}' along with its friends and rules?", self.config.profile));
+                    ui.add_space(5.0);
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if ui.button("Delete").clicked() {
+                            self.config.remove_active_profile();
+                            self.profile_delete_open = false;
+                        };
+                        if ui.button("Cancel").clicked() {
+                            self.profile_delete_open = false;
+                        };
+                    });
+                })
+                .should_close()
+        {
+            self.profile_delete_open = false;
+        };
+
+        // Edits are sent to the engine, which owns the friends state and applies their side effects
+        if self.config != previous_config {
+            self.config_revision += 1;
+            self.engine.send(Message::UpdateConfig(Box::new(self.config.clone()), self.config_revision));
+        }
     }
 }
 
-impl Default for FriendsNotifierApp {
-    fn default() -> Self {
-        let (g_sx, g_rx) = channel::<Message>();
-        let (s_sx, _) = channel::<Message>();
-        Self {
-            friends: vec![Friend::default()],
-            g_sx,
-            g_rx,
-            s_sx,
-            client_status: false,
-            settings_open: false,
-            native_notification: false,
-            volume: 100,
-            notify_away_status: false,
-            notify_in_game_status: true,
-            dark_mode: true,
+// Weekdays as rows and hours as columns, cells get greener the more logins they had
+fn login_heatmap(ui: &mut egui::Ui, stats: &FriendStats) {
+    const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    let cell = ui.available_width() / 24.0;
+    let (rect, response) = ui.allocate_exact_size(Vec2::new(cell * 24.0, cell * 7.0), Sense::hover());
+    let max = stats.logins.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let empty = ui.visuals().faint_bg_color;
+    for (day, hours) in stats.logins.iter().enumerate() {
+        for (hour, count) in hours.iter().enumerate() {
+            let min = rect.min + Vec2::new(hour as f32 * cell, day as f32 * cell);
+            let color = match count {
+                0 => empty,
+                _ => Color32::from_rgb(0, 200, 83).gamma_multiply(0.25 + 0.75 * *count as f32 / max as f32),
+            };
+            ui.painter().rect_filled(Rect::from_min_size(min, Vec2::splat(cell - 1.0)), 1.0, color);
         }
     }
+    if let Some(pos) = response.hover_pos() {
+        let hour = (((pos.x - rect.min.x) / cell) as usize).min(23);
+        let day = (((pos.y - rect.min.y) / cell) as usize).min(6);
+        response.on_hover_text(format!("{} {hour:02}:00, {} logins", WEEKDAYS[day], stats.logins[day][hour]));
+    }
 }
 
-impl Default for Friend {
-    fn default() -> Self {
-        Self {
-            uuid: Uuid::new_v4(),
-            timer_id: Uuid::new_v4(),
-            enabled: false,
-            name: "".to_string(),
-            sound: Sound {
-                label: crate::ASSET_SOUNDS[0].0.to_string(),
-                path: crate::ASSET_SOUNDS[0].1.to_string(),
-            },
-            notify_timer: 5,
-            is_repeat: false,
-            status: FriendStatus::default(),
-        }
+fn status_icon(status: FriendStatus) -> Image<'static> {
+    match status {
+        FriendStatus::Online => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_GREEN),
+        FriendStatus::InGame => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_CYAN),
+        FriendStatus::Mobile => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_GREY),
+        FriendStatus::Away => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_YELLOW),
+        FriendStatus::Offline => Image::new(crate::ASSET_ICON_CIRCLE_FILLED_RED),
     }
 }
 
-// Friend uniqueness depends on generated uuid, otherwise two different added friends
-// could be the same if their name was equal, and during logical comparison
-// only the first one would be used for such operations
-impl PartialEq for Friend {
-    fn eq(&self, other: &Self) -> bool {
-        self.uuid == other.uuid
+// Friends whose riot id contains the query, sorted by name
+fn search_friends<'a>(api_friends: &'a [ApiFriend], query: &str) -> Vec<&'a ApiFriend> {
+    let query = query.to_lowercase();
+    let mut found: Vec<&ApiFriend> = api_friends.iter().filter(|f| f.riot_id.to_lowercase().contains(&query)).collect();
+    found.sort_by_key(|f| f.riot_id.to_lowercase());
+    found
+}
+
+// Entries of the history log, oldest first, and the stats computed from them
+#[derive(Default)]
+struct LoadedHistory {
+    // Revision of the log when the entries were loaded
+    revision: u64,
+    entries: Vec<HistoryEntry>,
+    // Computed the first time a friend is shown, dropped when the entries are loaded again
+    stats: HashMap<Uuid, FriendStats>,
+}
+
+impl LoadedHistory {
+    fn load(&mut self, history: &History) {
+        self.revision = history.revision();
+        self.entries = history.load();
+        self.stats.clear();
+    }
+
+    fn stats(&mut self, friend: Uuid, now: i64) -> &FriendStats {
+        self.stats.entry(friend).or_insert_with(|| stats::compute(&self.entries, friend, now))
     }
 }
 
-impl From<&ApiFriend> for FriendStatus {
-    fn from(value: &ApiFriend) -> Self {
-        match &*value.availability {
-            "chat" => FriendStatus::Online,
-            "dnd" => FriendStatus::InGame,
-            "mobile" => FriendStatus::Mobile,
-            "away" => FriendStatus::Away,
-            _ => FriendStatus::Offline,
+// Shared widgets and senders used while drawing the friend rows
+struct RowContext<'a> {
+    api_friends: &'a [ApiFriend],
+    groups: &'a [FriendGroup],
+    sounds: &'a SoundChoices<'a>,
+    engine: &'a EngineHandle,
+    icon_check: &'a Image<'a>,
+    icon_repeat: &'a Image<'a>,
+    icon_dash: &'a Image<'a>,
+}
+
+// Sounds offered by the sound selectors, built-in ones followed by the imported ones
+struct SoundChoices<'a> {
+    sounds: &'a [Sound],
+    s_sx: &'a Sender<Message>,
+}
+
+// Sound selector widget, plays the sound as a preview when it changes
+fn sound_combobox(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, sound: &mut Sound, choices: &SoundChoices) {
+    // Imported sounds can be renamed, the label is taken from the current list
+    let selected = choices.sounds.iter().find(|s| s.path == sound.path).map(|s| s.label.as_str()).unwrap_or(&sound.label);
+    ComboBox::from_id_salt(id_salt).selected_text(selected).show_ui(ui, |ui| {
+        for choice in choices.sounds {
+            if ui.selectable_label(sound.path == choice.path, &choice.label).clicked() && sound.path != choice.path {
+                *sound = choice.clone();
+                let _ = choices.s_sx.send(Message::PlaySound(sound.path.clone(), 100));
+            }
         }
-    }
+    });
+}
+
+// Status selector of a rule, None stands for any status
+fn status_combobox(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, status: &mut Option<FriendStatus>) {
+    let all = [FriendStatus::Online, FriendStatus::InGame, FriendStatus::Mobile, FriendStatus::Away, FriendStatus::Offline];
+    ComboBox::from_id_salt(id_salt)
+        .width(60.0)
+        .selected_text(status.map(|s| s.label()).unwrap_or("Any"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(status, None, "Any");
+            for s in all {
+                ui.selectable_value(status, Some(s), s.label());
+            }
+        });
+}
+
+// Single rule of the rules modal, returns whether it was deleted
+fn rule_row(ui: &mut egui::Ui, rule: &mut Rule, friends: &[Friend], groups: &[FriendGroup], icon_check: &Image, icon_dash: &Image, sounds: &SoundChoices) -> bool {
+    let mut deleted = false;
+    let uuid = rule.uuid;
+    ui.horizontal(|ui| {
+        ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+        if ui
+            .add(Button::selectable(rule.enabled, icon_check.clone()).frame_when_inactive(true))
+            .on_hover_text("Enable rule")
+            .clicked()
+        {
+            rule.enabled = !rule.enabled;
+        };
+        status_combobox(ui, ("rule_from", uuid), &mut rule.from);
+        ui.label("to");
+        status_combobox(ui, ("rule_to", uuid), &mut rule.to);
+        let target_text = rule.target.label(friends, groups);
+        ComboBox::from_id_salt(("rule_target", uuid)).width(120.0).selected_text(target_text).show_ui(ui, |ui| {
+            ui.selectable_value(&mut rule.target, RuleTarget::Anyone, "Anyone");
+            for g in groups {
+                ui.selectable_value(&mut rule.target, RuleTarget::Group(g.uuid), format!("Group {}", g.name));
+            }
+            for f in friends.iter().filter(|f| !f.name.is_empty()) {
+                ui.selectable_value(&mut rule.target, RuleTarget::Friend(f.uuid), &f.name);
+            }
+        });
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.style_mut().spacing.button_padding = [10.0, 0.0].into();
+            if ui.add(Button::image(icon_dash.clone())).on_hover_text("Delete rule").clicked() {
+                deleted = true;
+            }
+        });
+    });
+    ui.horizontal(|ui| {
+        ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+        ui.add_space(22.0);
+        ComboBox::from_id_salt(("rule_action", uuid))
+            .width(60.0)
+            .selected_text(rule.action.label())
+            .show_ui(ui, |ui| {
+                for action in RuleAction::kinds() {
+                    if ui.selectable_label(action.label() == rule.action.label(), action.label()).clicked() && action.label() != rule.action.label() {
+                        rule.action = action;
+                    }
+                }
+            });
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.add(DragValue::new(&mut rule.cooldown).range(0..=3600).suffix("s"))
+                .on_hover_text("Cooldown before the rule fires again for the same friend");
+            match &mut rule.action {
+                RuleAction::Sound(sound) => {
+                    ui.add(DragValue::new(&mut rule.volume).range(0..=100).suffix("%")).on_hover_text("Volume of the sound");
+                    sound_combobox(ui, ("rule_sound", uuid), sound, sounds);
+                }
+                RuleAction::Toast(message) => {
+                    ui.add_sized(
+                        ui.available_size(),
+                        TextEdit::singleline(message).hint_text("{name} is now {to}").vertical_align(Align::Center),
+                    );
+                }
+                RuleAction::Webhook(url) => {
+                    ui.add_sized(ui.available_size(), TextEdit::singleline(url).hint_text("https://").vertical_align(Align::Center));
+                }
+                RuleAction::Command(command) => {
+                    ui.add_sized(
+                        ui.available_size(),
+                        TextEdit::singleline(command)
+                            .hint_text("Command, uses $FRIEND_NAME, $FROM_STATUS, $TO_STATUS")
+                            .vertical_align(Align::Center),
+                    );
+                }
+            }
+        });
+    });
+    deleted
+}
+
+// Group header row, toggles notifications for the whole group and holds its default sound
+fn group_header(ui: &mut egui::Ui, group: &mut FriendGroup, icon_check: &Image, icon_plus: &Image, icon_dash: &Image, sounds: &SoundChoices) -> Option<RowAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.style_mut().spacing.item_spacing = [2.0, 0.0].into();
+        if ui
+            .add(Button::selectable(group.enabled, icon_check.clone()).frame_when_inactive(true))
+            .on_hover_text("Notifications for this group")
+            .clicked()
+        {
+            group.enabled = !group.enabled;
+        };
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.style_mut().spacing.button_padding = [10.0, 0.0].into();
+            if ui.add(Button::image(icon_dash.clone())).on_hover_text("Delete group").clicked() {
+                action = Some(RowAction::DeleteGroup(group.uuid));
+            }
+            if ui.add(Button::image(icon_plus.clone())).on_hover_text("Add friend to group").clicked() {
+                action = Some(RowAction::AddToGroup(group.uuid));
+            }
+            ui.separator();
+            sound_combobox(ui, ("group_sound", group.uuid), &mut group.sound, sounds);
+            ui.separator();
+            ui.add_sized(
+                ui.available_size(),
+                TextEdit::singleline(&mut group.name).hint_text("Group name").vertical_align(Align::Center),
+            );
+        });
+    });
+    action
+}
+
+// Single friend row, returns the action requested through its buttons or context menu
+fn friend_row(ui: &mut egui::Ui, friend: &mut Friend, row: &RowContext) -> Option<RowAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.style_mut().spacing.item_spacing = [2.0, 0.0].into();
+        // Friend notification enabling button widget
+        if ui.add(Button::selectable(friend.enabled, row.icon_check.clone()).frame_when_inactive(true)).clicked() {
+            friend.enabled = !friend.enabled;
+        };
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.style_mut().spacing.button_padding = [10.0, 0.0].into();
+            if ui.add(Button::image(row.icon_dash.clone())).on_hover_text("Remove friend").clicked() {
+                action = Some(RowAction::Delete(friend.uuid));
+            }
+            // Friend status icon widget
+            let friend_status_img_res = ui.add(status_icon(friend.status));
+            Tooltip::for_enabled(&friend_status_img_res).show(|ui| {
+                ui.label(friend.status.label());
+                for line in friend.presence.details() {
+                    ui.label(RichText::from(line).size(9.0));
+                }
+            });
+
+            ui.separator();
+            // Repeat notification button and value widgets, the value counts down to the next reminder while one is pending
+            let mut timer = DragValue::new(&mut friend.notify_timer).range(5..=100).suffix("s").update_while_editing(false);
+            if let Some(remaining) = row.engine.reminder_remaining(friend.uuid).filter(|_| friend.enabled) {
+                let secs = remaining.as_secs_f32().ceil() as u64;
+                timer = timer.custom_formatter(move |_, _| secs.to_string());
+            }
+            ui.add_enabled(!friend.enabled, timer);
+            if ui.add(Button::selectable(friend.is_repeat, row.icon_repeat.clone()).frame_when_inactive(true)).clicked() {
+                friend.is_repeat = !friend.is_repeat;
+            };
+            ui.separator();
+            // Friend specific sound, combobox selector widget
+            sound_combobox(ui, ("friend_sound", friend.uuid), &mut friend.sound, row.sounds);
+            ui.separator();
+            // Note shown after a rename was detected, clicking it dismisses the note
+            if let Some(previous_name) = &friend.previous_name
+                && ui
+                    .add(Button::new(RichText::from("renamed").italics().size(8.0)).frame(false))
+                    .on_hover_text(format!("Renamed from {previous_name}"))
+                    .clicked()
+            {
+                friend.previous_name = None;
+            }
+            // Finally the friend name box widget, flagged when the name is not in the friends list
+            // Editing the name points the row to a different friend, the puuid gets resolved again
+            let is_unmatched = !row.api_friends.is_empty() && !friend.name.is_empty() && !row.api_friends.iter().any(|f| friend.matches(f));
+            let mut name_edit = TextEdit::singleline(&mut friend.name).vertical_align(Align::Center).interactive(!friend.enabled);
+            if is_unmatched {
+                name_edit = name_edit.text_color(Color32::from_rgb(230, 60, 60));
+            }
+            let name_res = ui.add_sized(ui.available_size(), name_edit);
+            if name_res.changed() {
+                friend.puuid = None;
+                friend.previous_name = None;
+            }
+            // Autocomplete dropdown fed by the friends list while the name is being edited
+            let picker_id = Id::new(("name_picker", friend.uuid));
+            if name_res.gained_focus() || name_res.changed() {
+                Popup::open_id(ui.ctx(), picker_id);
+            }
+            if !friend.enabled && !row.api_friends.is_empty() {
+                Popup::from_response(&name_res)
+                    .id(picker_id)
+                    .open_memory(None)
+                    .close_behavior(PopupCloseBehavior::CloseOnClickOutside)
+                    .width(name_res.rect.width())
+                    .show(|ui| {
+                        ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                            for api_friend in search_friends(row.api_friends, &friend.name) {
+                                ui.horizontal(|ui| {
+                                    ui.style_mut().spacing.item_spacing = [4.0, 0.0].into();
+                                    ui.add(status_icon(api_friend.into()));
+                                    if ui.selectable_label(false, &api_friend.riot_id).clicked() {
+                                        friend.name = api_friend.riot_id.clone();
+                                        friend.puuid = Some(api_friend.puuid.clone());
+                                        friend.previous_name = None;
+                                        Popup::close_id(ui.ctx(), picker_id);
+                                    }
+                                });
+                            }
+                        });
+                    });
+            }
+            // Game end notification and moving the friend between groups are done from the name context menu
+            name_res.context_menu(|ui| {
+                ui.add(Slider::new(&mut friend.volume, 0..=100).text("Volume").suffix("%"))
+                    .on_hover_text("Volume of this friend notifications, scaled by the master volume");
+                ui.separator();
+                ui.checkbox(&mut friend.notify_game_end, "Notify when their game ends");
+                ui.add_enabled_ui(friend.notify_game_end, |ui| {
+                    sound_combobox(ui, ("game_end_sound", friend.uuid), &mut friend.game_end_sound, row.sounds);
+                    ui.add(Slider::new(&mut friend.game_end_volume, 0..=100).text("Volume").suffix("%"));
+                    ui.add(TextEdit::singleline(&mut friend.game_end_message).hint_text("{name} finished their game"))
+                        .on_hover_text("{name} is replaced with the friend name");
+                });
+                ui.separator();
+                ui.label(RichText::from("Move to").italics());
+                if ui.add_enabled(friend.group.is_some(), Button::new("No group")).clicked() {
+                    action = Some(RowAction::MoveTo(friend.uuid, None));
+                }
+                for g in row.groups {
+                    if ui.add_enabled(friend.group != Some(g.uuid), Button::new(&g.name)).clicked() {
+                        action = Some(RowAction::MoveTo(friend.uuid, Some(g.uuid)));
+                    }
+                }
+            });
+            if is_unmatched {
+                name_res.on_hover_text("Not found in your friends list");
+            }
+        })
+    });
+    action
 }
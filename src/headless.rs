@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+use lolfriendsnotifier::config::{Config, Friend};
+use lolfriendsnotifier::engine::Message;
+use lolfriendsnotifier::history::History;
+use lolfriendsnotifier::migrations;
+use lolfriendsnotifier::{APP_VERSION, service};
+
+const USAGE: &str = "Commands: add <name#tag>, remove <name#tag>, enable <name#tag|all>, disable <name#tag|all>, list. \
+Every friend is enabled on start, enable and disable only last until the app stops";
+
+// Commands accepted on the command line after --headless and on stdin while running
+#[derive(Debug, PartialEq)]
+enum Command {
+    Add(String),
+    Remove(String),
+    Enable(String),
+    Disable(String),
+    List,
+}
+
+/// Runs the notifier without a window, using the configuration persisted by the gui
+/// Events are written to stdout and to a log file next to the persisted configuration
+/// The given profile is made active, the last active one is used otherwise
+pub fn run(args: &[String], profile: Option<&str>) -> ! {
+    attach_console();
+    let store = ConfigStore::new();
+    init_logging(store.path.as_ref().and_then(|p| p.parent()).map(PathBuf::from));
+    info!("Friends Notifier v{APP_VERSION} running headless");
+
+    let mut config = store.load();
+    if let Some(name) = profile
+        && !config.switch_profile(name)
+    {
+        warn!("Unknown profile '{name}', available: {}", config.profile_names().join(", "));
+    }
+    info!("Using profile {}", config.profile);
+    // Unlike in the gui there is no window to enable friends from, so they all start enabled
+    config.friends.iter_mut().for_each(|f| f.enabled = true);
+    let history = History::new(store.path.as_ref().and_then(|p| p.parent()).map(|dir| dir.join("history.jsonl")));
+    let engine = service::start(config.clone(), history, false, || {}).engine;
+
+    // Edits are sent to the engine the same way the gui does, see FriendsNotifierApp::sync_snapshot
+    let mut revision = 0;
+    let initial = (!args.is_empty()).then(|| args.join(" "));
+    let lines = std::io::stdin().lock().lines().map_while(Result::ok);
+    for line in initial.into_iter().chain(lines) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(command) = parse_command(&line) else {
+            warn!("Unknown command '{}'. {USAGE}", line.trim());
+            continue;
+        };
+        if let Some(snapshot) = engine.snapshot_since(0)
+            && snapshot.config_revision == revision
+        {
+            config = snapshot.config;
+        }
+        if command == Command::List {
+            list_friends(&config);
+            continue;
+        }
+        if apply_command(&mut config, command) {
+            revision += 1;
+            engine.send(Message::UpdateConfig(Box::new(config.clone()), revision));
+            store.save(&config);
+        }
+    }
+    // Without stdin, e.g. when autostarted, keep notifying until the process is stopped
+    loop {
+        std::thread::park();
+    }
+}
+
+// Release builds use the windows subsystem and start without a console
+// Commands and events go through the console the app was started from, or a new one when started from a shortcut
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{ATTACH_PARENT_PROCESS, AllocConsole, AttachConsole};
+    unsafe {
+        if AttachConsole(ATTACH_PARENT_PROCESS) == 0 {
+            AllocConsole();
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+fn init_logging(dir: Option<PathBuf>) {
+    let file = dir.map(|dir| fmt::layer().with_ansi(false).with_writer(tracing_appender::rolling::never(dir, "headless.log")));
+    let _ = tracing_subscriber::registry().with(fmt::layer()).with(file).try_init();
+}
+
+fn parse_command(line: &str) -> Option<Command> {
+    let (name, arg) = line.trim().split_once(' ').map(|(n, a)| (n, a.trim())).unwrap_or((line.trim(), ""));
+    match (name, arg) {
+        ("list", _) => Some(Command::List),
+        (_, "") => None,
+        ("add", _) => Some(Command::Add(arg.to_string())),
+        ("remove", _) => Some(Command::Remove(arg.to_string())),
+        ("enable", _) => Some(Command::Enable(arg.to_string())),
+        ("disable", _) => Some(Command::Disable(arg.to_string())),
+        _ => None,
+    }
+}
+
+// Applies the command to the configuration, returns whether it changed
+fn apply_command(config: &mut Config, command: Command) -> bool {
+    let has_name = |f: &Friend, name: &str| f.name.to_lowercase() == name.to_lowercase();
+    // "all" stands for every friend when enabling or disabling them
+    let is_named = |f: &Friend, name: &str| name == "all" || has_name(f, name);
+    match command {
+        Command::Add(name) => {
+            if config.friends.iter().any(|f| has_name(f, &name)) {
+                warn!("{name} is already tracked");
+                return false;
+            }
+            config.friends.push(Friend {
+                name: name.clone(),
+                enabled: true,
+                ..Default::default()
+            });
+            info!("Added {name}");
+        }
+        Command::Remove(name) => {
+            // Removing is not undoable, so "all" only ever matches a friend with that name
+            if name == "all" {
+                warn!("remove only takes a single friend, all is treated as a name");
+            }
+            let count = config.friends.len();
+            config.friends.retain(|f| !has_name(f, &name));
+            if config.friends.len() == count {
+                warn!("{name} is not tracked");
+                return false;
+            }
+            info!("Removed {name}");
+        }
+        Command::Enable(name) | Command::Disable(name) if !config.friends.iter().any(|f| is_named(f, &name)) => {
+            warn!("{name} is not tracked");
+            return false;
+        }
+        Command::Enable(name) => config.friends.iter_mut().filter(|f| is_named(f, &name)).for_each(|f| f.enabled = true),
+        Command::Disable(name) => config.friends.iter_mut().filter(|f| is_named(f, &name)).for_each(|f| f.enabled = false),
+        Command::List => return false,
+    }
+    true
+}
+
+fn list_friends(config: &Config) {
+    for f in config.friends.iter().filter(|f| !f.name.is_empty()) {
+        let group = config.groups.iter().find(|g| Some(g.uuid) == f.group).map(|g| format!(" [{}]", g.name)).unwrap_or_default();
+        let enabled = if f.enabled { "enabled" } else { "disabled" };
+        println!("{}{group}: {} ({enabled})", f.name, f.status.label());
+    }
+}
+
+// Configuration persisted by eframe, stored as a ron map next to the gui window state
+pub struct ConfigStore {
+    path: Option<PathBuf>,
+}
+
+impl ConfigStore {
+    pub fn new() -> Self {
+        Self {
+            path: eframe::storage_dir(crate::APP_ID).map(|dir| dir.join("app.ron")),
+        }
+    }
+
+    fn read(&self) -> HashMap<String, String> {
+        self.path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| ron::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn load(&self) -> Config {
+        let dir = self.path.as_ref().and_then(|p| p.parent());
+        self.read().get(eframe::APP_KEY).map(|s| migrations::load(s, dir)).unwrap_or_default()
+    }
+
+    // Other entries, such as the window state, are kept as they are
+    pub fn save(&self, config: &Config) {
+        let Some(path) = &self.path else { return };
+        let mut kv = self.read();
+        if let Ok(value) = ron::to_string(config) {
+            kv.insert(eframe::APP_KEY.to_string(), value);
+        }
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(s) = ron::ser::to_string_pretty(&kv, Default::default()) {
+            let _ = std::fs::write(path, s);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command(" add  Ahri#EUW "), Some(Command::Add("Ahri#EUW".to_string())));
+        assert_eq!(parse_command("enable all"), Some(Command::Enable("all".to_string())));
+        assert_eq!(parse_command("list"), Some(Command::List));
+        assert_eq!(parse_command("remove"), None);
+        assert_eq!(parse_command("invite Ahri#EUW"), None);
+    }
+
+    #[test]
+    fn applies_commands_by_case_insensitive_name() {
+        let mut config = Config::default();
+        assert!(apply_command(&mut config, Command::Add("Ahri#EUW".to_string())));
+        assert!(!apply_command(&mut config, Command::Add("ahri#euw".to_string())));
+        // Added friends are enabled right away, like the ones loaded on start
+        assert!(config.friends.iter().any(|f| f.name == "Ahri#EUW" && f.enabled));
+        assert!(apply_command(&mut config, Command::Disable("AHRI#EUW".to_string())));
+        assert!(config.friends.iter().any(|f| f.name == "Ahri#EUW" && !f.enabled));
+        assert!(!apply_command(&mut config, Command::Disable("Lux#EUW".to_string())));
+        assert!(apply_command(&mut config, Command::Remove("ahri#euw".to_string())));
+        assert!(!config.friends.iter().any(|f| f.name == "Ahri#EUW"));
+    }
+
+    #[test]
+    fn all_only_stands_for_every_friend_when_enabling_or_disabling() {
+        let mut config = Config::default();
+        config.friends.clear();
+        assert!(apply_command(&mut config, Command::Add("Ahri#EUW".to_string())));
+        assert!(apply_command(&mut config, Command::Add("Lux#EUW".to_string())));
+        assert!(apply_command(&mut config, Command::Disable("all".to_string())));
+        assert!(config.friends.iter().all(|f| !f.enabled));
+        assert!(!apply_command(&mut config, Command::Remove("all".to_string())));
+        assert_eq!(config.friends.len(), 2);
+        assert!(apply_command(&mut config, Command::Add("all".to_string())));
+        assert_eq!(config.friends.len(), 3);
+        assert!(apply_command(&mut config, Command::Remove("all".to_string())));
+        let names: Vec<&str> = config.friends.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["Ahri#EUW", "Lux#EUW"]);
+    }
+}
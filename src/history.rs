@@ -0,0 +1,197 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+use crate::presence::FriendStatus;
+
+/// Entry of the history log, stored as one json object per line
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HistoryEntry {
+    // Unix timestamp in seconds
+    pub timestamp: i64,
+    pub friend: Uuid,
+    // Riot id of the friend at the time of the event
+    pub name: String,
+    pub event: HistoryEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HistoryEvent {
+    // Accepted status change, recorded for every tracked friend whether it notifies or not
+    Status {
+        from: FriendStatus,
+        to: FriendStatus,
+    },
+    Notification {
+        body: String,
+    },
+    // Rule that fired, along with the label of its action and its transition and target when it fired
+    // Entries recorded before the summary was added have an empty one
+    Rule {
+        rule: Uuid,
+        action: String,
+        #[serde(default)]
+        summary: String,
+    },
+}
+
+/// Append-only JSON Lines log of the presence transitions and notifications
+/// Without a path, e.g. in demo mode, nothing is recorded
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    // Incremented on every write, shared by the clones so readers know when to load the log again
+    revision: Arc<AtomicU64>,
+}
+
+impl HistoryEntry {
+    pub fn new(friend: Uuid, name: &str, event: HistoryEvent) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp(),
+            friend,
+            name: name.to_string(),
+            event,
+        }
+    }
+
+    pub fn local_time(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.timestamp, 0).single()
+    }
+
+    /// Whether the entry matches the filters of the history panel, dates are inclusive and in local time
+    pub fn matches(&self, friend: Option<Uuid>, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+        let date = self.local_time().map(|t| t.date_naive());
+        friend.is_none_or(|f| f == self.friend) && from.is_none_or(|d| date.is_some_and(|t| t >= d)) && to.is_none_or(|d| date.is_some_and(|t| t <= d))
+    }
+}
+
+impl HistoryEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            HistoryEvent::Status { from, to } => format!("{} → {}", from.label(), to.label()),
+            HistoryEvent::Notification { body } => format!("Notified: {body}"),
+            HistoryEvent::Rule { action, summary, .. } if !summary.is_empty() => format!("{action} rule fired ({summary})"),
+            HistoryEvent::Rule { action, .. } => format!("{action} rule fired"),
+        }
+    }
+}
+
+impl History {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path, revision: Arc::default() }
+    }
+
+    /// Changes every time an entry is appended or the log is pruned
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    pub fn append(&self, entry: &HistoryEntry) {
+        let Some(path) = &self.path else { return };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(line) = serde_json::to_string(entry)
+            && let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path)
+        {
+            let _ = writeln!(file, "{line}");
+            self.revision.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Every entry in the log, oldest first, lines that cannot be parsed are skipped
+    pub fn load(&self) -> Vec<HistoryEntry> {
+        let Some(content) = self.path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) else {
+            return vec![];
+        };
+        content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+    }
+
+    /// Drops the entries older than the retention period, 0 keeps them forever
+    pub fn prune(&self, retention_days: u32) {
+        let Some(path) = &self.path else { return };
+        if retention_days == 0 {
+            return;
+        }
+        let cutoff = Utc::now().timestamp() - retention_days as i64 * 24 * 60 * 60;
+        let entries = self.load();
+        if entries.iter().all(|e| e.timestamp >= cutoff) {
+            return;
+        }
+        let kept: String = entries
+            .iter()
+            .filter(|e| e.timestamp >= cutoff)
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .map(|line| line + "\n")
+            .collect();
+        let _ = std::fs::write(path, kept);
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appended_entries_are_loaded_back_until_pruned() {
+        let path = std::env::temp_dir().join(format!("history-{}.jsonl", Uuid::new_v4()));
+        let history = History::new(Some(path.clone()));
+        let friend = Uuid::new_v4();
+        let mut old = HistoryEntry::new(
+            friend,
+            "Ahri#EUW",
+            HistoryEvent::Notification {
+                body: "Ahri#EUW is Online!".to_string(),
+            },
+        );
+        old.timestamp -= 10 * 24 * 60 * 60;
+        let recent = HistoryEntry::new(
+            friend,
+            "Ahri#EUW",
+            HistoryEvent::Status {
+                from: FriendStatus::Online,
+                to: FriendStatus::InGame,
+            },
+        );
+        // Clones share the revision, the gui reads the log written by the engine
+        let reader = history.clone();
+        history.append(&old);
+        history.append(&recent);
+        assert_eq!(history.load(), vec![old.clone(), recent.clone()]);
+        assert_eq!(reader.revision(), 2);
+
+        history.prune(0);
+        assert_eq!(history.load().len(), 2);
+        assert_eq!(reader.revision(), 2);
+        history.prune(7);
+        assert_eq!(history.load(), vec![recent.clone()]);
+        assert_eq!(reader.revision(), 3);
+
+        let today = recent.local_time().unwrap().date_naive();
+        assert!(recent.matches(Some(friend), Some(today), Some(today)));
+        assert!(!recent.matches(Some(Uuid::new_v4()), None, None));
+        assert!(!recent.matches(None, today.succ_opt(), None));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rule_entries_describe_the_rule_even_when_recorded_without_a_summary() {
+        let rule = Uuid::new_v4();
+        let event = HistoryEvent::Rule {
+            rule,
+            action: "Toast".to_string(),
+            summary: "Any → In Game, Group Duo".to_string(),
+        };
+        assert_eq!(event.describe(), "Toast rule fired (Any → In Game, Group Duo)");
+
+        let old: HistoryEvent = serde_json::from_str(&format!(r#"{{"type":"rule","rule":"{rule}","action":"Sound"}}"#)).unwrap();
+        assert_eq!(old.describe(), "Sound rule fired");
+    }
+}
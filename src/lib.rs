@@ -0,0 +1,40 @@
+//! Core of the friends notifier: presence model, notification rules, reminders scheduler and audio service
+//! The engine thread ties them together, front-ends only send it messages and read its snapshots
+
+pub mod assets;
+pub mod audio;
+pub mod config;
+pub mod debounce;
+pub mod engine;
+pub mod export;
+pub mod history;
+pub mod migrations;
+pub mod presence;
+pub mod rules;
+pub mod scheduler;
+pub mod service;
+pub mod source;
+pub mod stats;
+pub mod websocket;
+
+pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+// Names the folders of the persisted state and of the installed assets
+pub const APP_ID: &str = "friends_notifier";
+
+// Built-in sounds, their files are loaded at runtime once from the directory found by `assets::assets_dir`
+// and the audio thread falls back to embedded copies when they are missing
+pub const ASSET_SOUNDS: [(&str, &str); 13] = [
+    ("Sound 1", "assets/notification-1.mp3"),
+    ("Sound 2", "assets/notification-2.mp3"),
+    ("Sound 3", "assets/notification-3.mp3"),
+    ("Sound 4", "assets/notification-4.mp3"),
+    ("Sound 5", "assets/notification-5.mp3"),
+    ("Sound 6", "assets/notification-6.mp3"),
+    ("Sound 7", "assets/notification-7.mp3"),
+    ("Sound 8", "assets/notification-8.mp3"),
+    ("Sound 9", "assets/notification-9.mp3"),
+    ("Sound 10", "assets/notification-10.mp3"),
+    ("Sound 11", "assets/notification-11.mp3"),
+    ("Sound 12", "assets/notification-12.mp3"),
+    ("Sound 13", "assets/notification-13.mp3"),
+];
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+/// Version of the persisted configuration layout, stored in `Config::schema_version`
+/// Bump it along with a new step in MIGRATIONS whenever a field is renamed or changes type
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Step i reads a blob of version i and writes it in the layout of version i + 1
+const MIGRATIONS: [fn(&str) -> Option<String>; SCHEMA_VERSION as usize] = [v0_to_v1];
+
+// Only reads the version, blobs without one were persisted before versioning
+#[derive(Deserialize)]
+struct Versioned {
+    #[serde(default)]
+    schema_version: u32,
+}
+
+/// Version of the persisted blob, None when it cannot be parsed
+pub fn schema_version(blob: &str) -> Option<u32> {
+    ron::from_str::<Versioned>(blob).ok().map(|v| v.schema_version)
+}
+
+/// Runs the blob through every migration from its version up to the current one
+/// Blobs from a newer version are read as they are, fields unknown to this version are lost
+pub fn migrate(blob: &str) -> Option<Config> {
+    let version = schema_version(blob)?;
+    let mut blob = blob.to_string();
+    for step in MIGRATIONS.iter().skip(version as usize) {
+        blob = step(&blob)?;
+    }
+    let mut config: Config = ron::from_str(&blob).ok()?;
+    config.schema_version = SCHEMA_VERSION;
+    Some(config)
+}
+
+/// Loads the persisted configuration, falling back to the default one when it cannot be read
+/// Blobs that are migrated or unreadable are first copied to `backup_dir`, so nothing is lost if a migration goes wrong
+pub fn load(blob: &str, backup_dir: Option<&Path>) -> Config {
+    let version = schema_version(blob);
+    let config = migrate(blob);
+    if version != Some(SCHEMA_VERSION) || config.is_none() {
+        let name = match (version, &config) {
+            (Some(v), Some(_)) => format!("app-schema-{v}.ron.bak"),
+            _ => "app-unreadable.ron.bak".to_string(),
+        };
+        // A backup of the same version is kept as it is, it holds the state before the first migration
+        if let Some(path) = backup_dir.map(|dir| dir.join(name))
+            && !path.exists()
+        {
+            let _ = std::fs::write(&path, blob);
+            info!("Previous configuration saved to {}", path.display());
+        }
+    }
+    let Some(config) = config else {
+        warn!("Could not read the saved configuration, starting from the default one");
+        return Config::default();
+    };
+    if let Some(v) = version.filter(|v| *v != SCHEMA_VERSION) {
+        info!("Configuration migrated from schema {v} to {SCHEMA_VERSION}");
+    }
+    config
+}
+
+// Blobs without a version are the whole app state of release 1.0.5 and earlier
+// Read with a frozen copy of that layout and written with only the fields it maps to, the others get their defaults
+fn v0_to_v1(blob: &str) -> Option<String> {
+    let state: v0::State = ron::from_str(blob).ok()?;
+    let friends = state
+        .friends
+        .into_iter()
+        .map(|f| v1::Friend {
+            uuid: f.uuid,
+            name: f.name,
+            sound: v1::Sound {
+                label: f.sound.label,
+                path: f.sound.path,
+            },
+            is_repeat: f.is_repeat,
+            notify_timer: f.notify_timer,
+        })
+        .collect();
+    ron::to_string(&v1::Config {
+        schema_version: 1,
+        friends,
+        native_notification: state.native_notification,
+        volume: state.volume,
+        notify_away_status: state.notify_away_status,
+        notify_in_game_status: state.notify_in_game_status,
+        dark_mode: state.dark_mode,
+    })
+    .ok()
+}
+
+// Layouts as they were persisted, they must not change along with Config
+
+mod v0 {
+    use serde::Deserialize;
+    use uuid::Uuid;
+
+    // FriendsNotifierApp of release 1.0.5, its timer_id is not kept
+    #[derive(Deserialize)]
+    #[serde(default)]
+    pub struct State {
+        pub friends: Vec<Friend>,
+        pub native_notification: bool,
+        pub volume: u8,
+        pub notify_away_status: bool,
+        pub notify_in_game_status: bool,
+        pub dark_mode: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Friend {
+        pub uuid: Uuid,
+        pub name: String,
+        pub sound: Sound,
+        pub is_repeat: bool,
+        pub notify_timer: u16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Sound {
+        pub label: String,
+        pub path: String,
+    }
+
+    impl Default for State {
+        fn default() -> Self {
+            Self {
+                friends: vec![],
+                native_notification: false,
+                volume: 100,
+                notify_away_status: false,
+                notify_in_game_status: true,
+                dark_mode: true,
+            }
+        }
+    }
+}
+
+mod v1 {
+    use serde::Serialize;
+    use uuid::Uuid;
+
+    // Only the fields written by v0_to_v1
+    #[derive(Serialize)]
+    pub struct Config {
+        pub schema_version: u32,
+        pub friends: Vec<Friend>,
+        pub native_notification: bool,
+        pub volume: u8,
+        pub notify_away_status: bool,
+        pub notify_in_game_status: bool,
+        pub dark_mode: bool,
+    }
+
+    #[derive(Serialize)]
+    pub struct Friend {
+        pub uuid: Uuid,
+        pub name: String,
+        pub sound: Sound,
+        pub is_repeat: bool,
+        pub notify_timer: u16,
+    }
+
+    #[derive(Serialize)]
+    pub struct Sound {
+        pub label: String,
+        pub path: String,
+    }
+}
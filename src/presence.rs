@@ -0,0 +1,497 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Friend as listed by the League client, shared by every presence source
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiFriend {
+    pub puuid: String,
+    pub riot_id: String,
+    pub availability: String,
+    pub group_name: String,
+    pub presence: Presence,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FriendStatus {
+    Online,
+    InGame,
+    Mobile,
+    Away,
+    #[default]
+    Offline,
+}
+
+/// Game details of a friend parsed from the `lol` presence fields of the friends payload
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Presence {
+    pub game_phase: GamePhase,
+    pub queue_type: Option<String>,
+    pub champion_id: Option<u32>,
+    // Milliseconds since the epoch at which the current phase started
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum GamePhase {
+    #[default]
+    OutOfGame,
+    InQueue,
+    ChampionSelect,
+    InGame,
+    Spectating,
+    Other(String),
+}
+
+impl Presence {
+    /// Parses the presence fields, values are always sent as strings by the client
+    pub fn from_fields(lol: &HashMap<String, String>) -> Self {
+        let field = |key: &str| lol.get(key).map(|v| v.trim()).filter(|v| !v.is_empty());
+        Self {
+            game_phase: field("gameStatus").map(GamePhase::from).unwrap_or_default(),
+            queue_type: field("gameQueueType").map(|v| v.to_string()),
+            champion_id: field("championId").and_then(|v| v.parse().ok()).filter(|id| *id > 0),
+            timestamp: field("timeStamp").and_then(|v| v.parse().ok()).filter(|ts| *ts > 0),
+        }
+    }
+
+    pub fn queue_name(&self) -> Option<String> {
+        self.queue_type.as_deref().map(queue_name)
+    }
+
+    pub fn champion_name(&self) -> Option<String> {
+        self.champion_id.map(|id| champion_name(id).map(|n| n.to_string()).unwrap_or(format!("Champion {id}")))
+    }
+
+    /// Time spent in the current phase, None if the client did not send a timestamp
+    pub fn elapsed(&self) -> Option<Duration> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+        self.timestamp.map(|ts| Duration::from_millis(now.saturating_sub(ts)))
+    }
+
+    /// Lines shown in the friend row tooltip, e.g. ["In Game for 12 minutes", "Ranked Solo", "Ahri"]
+    /// Queue and champion are left over by the client after a game, so nothing is shown while out of game
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = vec![];
+        if self.game_phase == GamePhase::OutOfGame {
+            return lines;
+        }
+        match self.elapsed() {
+            Some(elapsed) => lines.push(format!("{} for {}", self.game_phase.label(), format_duration(elapsed))),
+            None => lines.push(self.game_phase.label().to_string()),
+        }
+        lines.extend(self.queue_name());
+        lines.extend(self.champion_name());
+        lines
+    }
+
+    /// Notification text for a friend that just changed presence,
+    /// e.g. "X started a Ranked Solo game as Ahri 2 minutes ago"
+    pub fn describe(&self, name: &str) -> Option<String> {
+        let queue = self.queue_name().map(|q| format!(" {q}")).unwrap_or_default();
+        let ago = self.elapsed().map(|e| format!(" {}", format_ago(e))).unwrap_or_default();
+        match self.game_phase {
+            GamePhase::InGame => {
+                let champion = self.champion_name().map(|c| format!(" as {c}")).unwrap_or_default();
+                let article = self.queue_name().map_or("a", |q| article(&q));
+                Some(format!("{name} started {article}{queue} game{champion}{ago}"))
+            }
+            GamePhase::ChampionSelect => Some(format!("{name} entered{queue} champion select{ago}")),
+            GamePhase::InQueue => Some(format!("{name} started queueing{queue}{ago}")),
+            _ => None,
+        }
+    }
+}
+
+impl GamePhase {
+    pub fn label(&self) -> &str {
+        match self {
+            GamePhase::OutOfGame => "Out of Game",
+            GamePhase::InQueue => "In Queue",
+            GamePhase::ChampionSelect => "Champion Select",
+            GamePhase::InGame => "In Game",
+            GamePhase::Spectating => "Spectating",
+            GamePhase::Other(status) => status,
+        }
+    }
+}
+
+impl From<&str> for GamePhase {
+    fn from(value: &str) -> Self {
+        match value {
+            "outOfGame" => GamePhase::OutOfGame,
+            "inQueue" => GamePhase::InQueue,
+            "championSelect" => GamePhase::ChampionSelect,
+            "inGame" => GamePhase::InGame,
+            "spectating" => GamePhase::Spectating,
+            other => GamePhase::Other(other.to_string()),
+        }
+    }
+}
+
+impl ApiFriend {
+    // Riot ids are case insensitive
+    pub fn is_named(&self, name: &str) -> bool {
+        self.riot_id.to_lowercase() == name.to_lowercase()
+    }
+}
+
+impl FriendStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FriendStatus::Online => "Online",
+            FriendStatus::InGame => "In Game",
+            FriendStatus::Mobile => "Mobile",
+            FriendStatus::Away => "Away",
+            FriendStatus::Offline => "Offline",
+        }
+    }
+}
+
+impl From<&ApiFriend> for FriendStatus {
+    fn from(value: &ApiFriend) -> Self {
+        match &*value.availability {
+            "chat" => FriendStatus::Online,
+            "dnd" => FriendStatus::InGame,
+            "mobile" => FriendStatus::Mobile,
+            "away" => FriendStatus::Away,
+            _ => FriendStatus::Offline,
+        }
+    }
+}
+
+/// Readable name of a queue type, unknown queues are title cased
+pub fn queue_name(queue_type: &str) -> String {
+    let name = match queue_type {
+        "RANKED_SOLO_5x5" => "Ranked Solo",
+        "RANKED_FLEX_SR" => "Ranked Flex",
+        "NORMAL" | "NORMAL_5x5" => "Normal",
+        "NORMAL_DRAFT" => "Normal Draft",
+        "QUICKPLAY" | "NORMAL_QUICKPLAY" => "Quickplay",
+        "SWIFTPLAY" => "Swiftplay",
+        "ARAM_UNRANKED_5x5" => "ARAM",
+        "CHERRY" => "Arena",
+        "URF" | "ARURF" | "ARURF_5X5" => "URF",
+        "ONEFORALL_5x5" => "One for All",
+        "NEXUSBLITZ" => "Nexus Blitz",
+        "ULTBOOK" => "Ultimate Spellbook",
+        "BOT" | "BOT_5x5" => "Co-op vs. AI",
+        "PRACTICETOOL" => "Practice Tool",
+        "CLASH" => "Clash",
+        "TFT" | "NORMAL_TFT" => "Teamfight Tactics",
+        "RANKED_TFT" => "Ranked TFT",
+        "RANKED_TFT_DOUBLE_UP" => "TFT Double Up",
+        "RANKED_TFT_TURBO" => "TFT Hyper Roll",
+        _ => {
+            return queue_type
+                .split('_')
+                .map(|w| {
+                    let w = w.to_lowercase();
+                    let mut chars = w.chars();
+                    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+        }
+    };
+    name.to_string()
+}
+
+// "an ARAM", "an Arena" but "a URF", acronyms starting with a U are read as "you"
+fn article(word: &str) -> &'static str {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some('A' | 'E' | 'I' | 'O' | 'a' | 'e' | 'i' | 'o'), _) => "an",
+        (Some('U' | 'u'), Some(c)) if c.is_lowercase() => "an",
+        _ => "a",
+    }
+}
+
+/// "12 minutes", "1 hour 5 minutes"
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let plural = |n: u64, unit: &str| format!("{n} {unit}{}", if n == 1 { "" } else { "s" });
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "less than a minute".to_string(),
+        (0, m) => plural(m, "minute"),
+        (h, 0) => plural(h, "hour"),
+        (h, m) => format!("{} {}", plural(h, "hour"), plural(m, "minute")),
+    }
+}
+
+/// "just now", "2 minutes ago"
+pub fn format_ago(duration: Duration) -> String {
+    match duration.as_secs() < 60 {
+        true => "just now".to_string(),
+        false => format!("{} ago", format_duration(duration)),
+    }
+}
+
+/// Champion names by id, ids that are not listed fall back to a generic name
+pub fn champion_name(id: u32) -> Option<&'static str> {
+    let name = match id {
+        1 => "Annie",
+        2 => "Olaf",
+        3 => "Galio",
+        4 => "Twisted Fate",
+        5 => "Xin Zhao",
+        6 => "Urgot",
+        7 => "LeBlanc",
+        8 => "Vladimir",
+        9 => "Fiddlesticks",
+        10 => "Kayle",
+        11 => "Master Yi",
+        12 => "Alistar",
+        13 => "Ryze",
+        14 => "Sion",
+        15 => "Sivir",
+        16 => "Soraka",
+        17 => "Teemo",
+        18 => "Tristana",
+        19 => "Warwick",
+        20 => "Nunu & Willump",
+        21 => "Miss Fortune",
+        22 => "Ashe",
+        23 => "Tryndamere",
+        24 => "Jax",
+        25 => "Morgana",
+        26 => "Zilean",
+        27 => "Singed",
+        28 => "Evelynn",
+        29 => "Twitch",
+        30 => "Karthus",
+        31 => "Cho'Gath",
+        32 => "Amumu",
+        33 => "Rammus",
+        34 => "Anivia",
+        35 => "Shaco",
+        36 => "Dr. Mundo",
+        37 => "Sona",
+        38 => "Kassadin",
+        39 => "Irelia",
+        40 => "Janna",
+        41 => "Gangplank",
+        42 => "Corki",
+        43 => "Karma",
+        44 => "Taric",
+        45 => "Veigar",
+        48 => "Trundle",
+        50 => "Swain",
+        51 => "Caitlyn",
+        53 => "Blitzcrank",
+        54 => "Malphite",
+        55 => "Katarina",
+        56 => "Nocturne",
+        57 => "Maokai",
+        58 => "Renekton",
+        59 => "Jarvan IV",
+        60 => "Elise",
+        61 => "Orianna",
+        62 => "Wukong",
+        63 => "Brand",
+        64 => "Lee Sin",
+        67 => "Vayne",
+        68 => "Rumble",
+        69 => "Cassiopeia",
+        72 => "Skarner",
+        74 => "Heimerdinger",
+        75 => "Nasus",
+        76 => "Nidalee",
+        77 => "Udyr",
+        78 => "Poppy",
+        79 => "Gragas",
+        80 => "Pantheon",
+        81 => "Ezreal",
+        82 => "Mordekaiser",
+        83 => "Yorick",
+        84 => "Akali",
+        85 => "Kennen",
+        86 => "Garen",
+        89 => "Leona",
+        90 => "Malzahar",
+        91 => "Talon",
+        92 => "Riven",
+        96 => "Kog'Maw",
+        98 => "Shen",
+        99 => "Lux",
+        101 => "Xerath",
+        102 => "Shyvana",
+        103 => "Ahri",
+        104 => "Graves",
+        105 => "Fizz",
+        106 => "Volibear",
+        107 => "Rengar",
+        110 => "Varus",
+        111 => "Nautilus",
+        112 => "Viktor",
+        113 => "Sejuani",
+        114 => "Fiora",
+        115 => "Ziggs",
+        117 => "Lulu",
+        119 => "Draven",
+        120 => "Hecarim",
+        121 => "Kha'Zix",
+        122 => "Darius",
+        126 => "Jayce",
+        127 => "Lissandra",
+        131 => "Diana",
+        133 => "Quinn",
+        134 => "Syndra",
+        136 => "Aurelion Sol",
+        141 => "Kayn",
+        142 => "Zoe",
+        143 => "Zyra",
+        145 => "Kai'Sa",
+        147 => "Seraphine",
+        150 => "Gnar",
+        154 => "Zac",
+        157 => "Yasuo",
+        161 => "Vel'Koz",
+        163 => "Taliyah",
+        164 => "Camille",
+        166 => "Akshan",
+        200 => "Bel'Veth",
+        201 => "Braum",
+        202 => "Jhin",
+        203 => "Kindred",
+        221 => "Zeri",
+        222 => "Jinx",
+        223 => "Tahm Kench",
+        233 => "Briar",
+        234 => "Viego",
+        235 => "Senna",
+        236 => "Lucian",
+        238 => "Zed",
+        240 => "Kled",
+        245 => "Ekko",
+        246 => "Qiyana",
+        254 => "Vi",
+        266 => "Aatrox",
+        267 => "Nami",
+        268 => "Azir",
+        350 => "Yuumi",
+        360 => "Samira",
+        412 => "Thresh",
+        420 => "Illaoi",
+        421 => "Rek'Sai",
+        427 => "Ivern",
+        429 => "Kalista",
+        432 => "Bard",
+        497 => "Rakan",
+        498 => "Xayah",
+        516 => "Ornn",
+        517 => "Sylas",
+        518 => "Neeko",
+        523 => "Aphelios",
+        526 => "Rell",
+        555 => "Pyke",
+        711 => "Vex",
+        777 => "Yone",
+        799 => "Ambessa",
+        800 => "Mel",
+        875 => "Sett",
+        876 => "Lillia",
+        887 => "Gwen",
+        888 => "Renata Glasc",
+        893 => "Aurora",
+        895 => "Nilah",
+        897 => "K'Sante",
+        901 => "Smolder",
+        902 => "Milio",
+        910 => "Hwei",
+        950 => "Naafiri",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn parses_presence_fields_of_every_phase() {
+        // In a lobby the client sends the queue being hosted, champion and timestamp are not set yet
+        let lobby = Presence::from_fields(&fields(&[("gameStatus", "hosting_RANKED_SOLO_5x5"), ("championId", ""), ("timeStamp", "0")]));
+        assert_eq!(lobby.game_phase, GamePhase::Other("hosting_RANKED_SOLO_5x5".to_string()));
+        assert_eq!((lobby.champion_id, lobby.timestamp), (None, None));
+
+        let champ_select = Presence::from_fields(&fields(&[("gameStatus", "championSelect"), ("gameQueueType", "RANKED_FLEX_SR"), ("championId", "0")]));
+        assert_eq!(champ_select.game_phase, GamePhase::ChampionSelect);
+        assert_eq!(champ_select.queue_name().as_deref(), Some("Ranked Flex"));
+        assert_eq!(champ_select.champion_id, None);
+
+        let in_game = Presence::from_fields(&fields(&[
+            ("gameStatus", "inGame"),
+            ("gameQueueType", "ARAM_UNRANKED_5x5"),
+            ("championId", "86"),
+            ("timeStamp", "1741000000000"),
+        ]));
+        assert_eq!(
+            in_game,
+            Presence {
+                game_phase: GamePhase::InGame,
+                queue_type: Some("ARAM_UNRANKED_5x5".to_string()),
+                champion_id: Some(86),
+                timestamp: Some(1741000000000),
+            }
+        );
+
+        let spectating = Presence::from_fields(&fields(&[("gameStatus", "spectating"), ("gameQueueType", " ")]));
+        assert_eq!(spectating.game_phase, GamePhase::Spectating);
+        assert_eq!(spectating.queue_type, None);
+        assert_eq!(Presence::from_fields(&HashMap::new()), Presence::default());
+    }
+
+    #[test]
+    fn unknown_queues_are_title_cased() {
+        assert_eq!(queue_name("RANKED_SOLO_5x5"), "Ranked Solo");
+        assert_eq!(queue_name("BRAWL_5x5"), "Brawl 5x5");
+        assert_eq!(queue_name("STRAWBERRY"), "Strawberry");
+    }
+
+    #[test]
+    fn describes_the_game_with_the_right_article() {
+        let mut presence = Presence {
+            game_phase: GamePhase::InGame,
+            queue_type: Some("ARAM_UNRANKED_5x5".to_string()),
+            champion_id: Some(86),
+            timestamp: None,
+        };
+        assert_eq!(presence.describe("Garen#EUW").as_deref(), Some("Garen#EUW started an ARAM game as Garen"));
+        presence.queue_type = Some("URF".to_string());
+        assert_eq!(presence.describe("Garen#EUW").as_deref(), Some("Garen#EUW started a URF game as Garen"));
+        presence.queue_type = Some("ULTBOOK".to_string());
+        presence.champion_id = Some(9999);
+        assert_eq!(
+            presence.describe("Garen#EUW").as_deref(),
+            Some("Garen#EUW started an Ultimate Spellbook game as Champion 9999")
+        );
+        presence.queue_type = None;
+        presence.champion_id = None;
+        assert_eq!(presence.describe("Garen#EUW").as_deref(), Some("Garen#EUW started a game"));
+
+        presence.game_phase = GamePhase::ChampionSelect;
+        presence.queue_type = Some("RANKED_SOLO_5x5".to_string());
+        assert_eq!(presence.describe("Garen#EUW").as_deref(), Some("Garen#EUW entered Ranked Solo champion select"));
+        presence.game_phase = GamePhase::InQueue;
+        assert_eq!(presence.describe("Garen#EUW").as_deref(), Some("Garen#EUW started queueing Ranked Solo"));
+        presence.game_phase = GamePhase::OutOfGame;
+        assert_eq!(presence.describe("Garen#EUW"), None);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "less than a minute");
+        assert_eq!(format_duration(Duration::from_secs(60)), "1 minute");
+        assert_eq!(format_duration(Duration::from_secs(12 * 60 + 30)), "12 minutes");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1 hour");
+        assert_eq!(format_duration(Duration::from_secs(2 * 3600 + 60)), "2 hours 1 minute");
+        assert_eq!(format_ago(Duration::from_secs(30)), "just now");
+        assert_eq!(format_ago(Duration::from_secs(120)), "2 minutes ago");
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::{Friend, FriendGroup, Sound};
+use crate::presence::FriendStatus;
+
+// Webhooks that do not answer in time are dropped, so a stuck server does not pile up threads
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// User defined notification, run when a friend status changes in the way described by the rule
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)] // new fields get default values when deserializing old state
+pub struct Rule {
+    pub uuid: Uuid,
+    pub enabled: bool,
+    // None matches any status
+    pub from: Option<FriendStatus>,
+    pub to: Option<FriendStatus>,
+    pub target: RuleTarget,
+    pub action: RuleAction,
+    // Seconds before the rule can fire again for the same friend
+    pub cooldown: u32,
+    // Volume in percent of the sound action
+    pub volume: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum RuleTarget {
+    #[default]
+    Anyone,
+    Friend(Uuid),
+    Group(Uuid),
+}
+
+// Toast messages support the '{name}', '{from}' and '{to}' placeholders
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum RuleAction {
+    Sound(Sound),
+    Toast(String),
+    // Url receiving a json POST with the friend name and both statuses
+    Webhook(String),
+    // Shell command, the friend name and statuses are passed as environment variables rather than placeholders
+    // so riot ids never end up being interpreted by the shell
+    Command(String),
+}
+
+/// Status change of a tracked friend, evaluated against the rules
+#[derive(Debug, Clone)]
+pub struct Transition<'a> {
+    pub friend: Uuid,
+    pub group: Option<Uuid>,
+    pub name: &'a str,
+    pub from: FriendStatus,
+    pub to: FriendStatus,
+}
+
+/// Last time each rule fired, keyed by (rule, friend)
+pub type Cooldowns = HashMap<(Uuid, Uuid), Instant>;
+
+impl Rule {
+    pub fn matches(&self, transition: &Transition) -> bool {
+        let is_target = match self.target {
+            RuleTarget::Anyone => true,
+            RuleTarget::Friend(uuid) => uuid == transition.friend,
+            RuleTarget::Group(uuid) => Some(uuid) == transition.group,
+        };
+        self.enabled && transition.from != transition.to && self.from.is_none_or(|s| s == transition.from) && self.to.is_none_or(|s| s == transition.to) && is_target
+    }
+
+    fn is_cooling_down(&self, friend: Uuid, cooldowns: &Cooldowns, now: Instant) -> bool {
+        cooldowns
+            .get(&(self.uuid, friend))
+            .is_some_and(|last| now.saturating_duration_since(*last) < Duration::from_secs(self.cooldown as u64))
+    }
+}
+
+/// Rules that should fire for the transition, in table order
+/// Rules that fired for the same friend less than their cooldown ago are left out
+pub fn evaluate<'a>(rules: &'a [Rule], transition: &Transition, cooldowns: &Cooldowns, now: Instant) -> Vec<&'a Rule> {
+    rules
+        .iter()
+        .filter(|r| r.matches(transition) && !r.is_cooling_down(transition.friend, cooldowns, now))
+        .collect()
+}
+
+/// Built-in notification driven by the global settings, applied alongside the rules table
+pub fn is_default_transition(from: FriendStatus, to: FriendStatus, notify_away_status: bool, notify_in_game_status: bool) -> bool {
+    // A status is considered "active" based on settings
+    let is_status_active = |status: FriendStatus| -> bool {
+        matches!(status, FriendStatus::Online) || (notify_away_status && matches!(status, FriendStatus::Away)) || (notify_in_game_status && matches!(status, FriendStatus::InGame))
+    };
+
+    // A meaningful change happens when the friend transitions between active and non-active states.
+    let is_meaningful_change = is_status_active(from) != is_status_active(to);
+
+    // Explicitly ignore notifications for becoming "Away" or "InGame" if their respective settings are off,
+    // even if it's considered a meaningful change (e.g., Online -> Away).
+    let is_ignored_transition = (!notify_away_status && matches!(to, FriendStatus::Away)) || (!notify_in_game_status && matches!(to, FriendStatus::InGame));
+
+    is_meaningful_change && !is_ignored_transition
+}
+
+impl Transition<'_> {
+    /// Replaces the placeholders of a rule text
+    pub fn format(&self, text: &str) -> String {
+        text.replace("{name}", self.name).replace("{from}", self.from.label()).replace("{to}", self.to.label())
+    }
+}
+
+impl Rule {
+    /// Transition and target of the rule, e.g. "Any → In Game, Group Duo"
+    pub fn summary(&self, friends: &[Friend], groups: &[FriendGroup]) -> String {
+        let label = |status: Option<FriendStatus>| status.map(|s| s.label()).unwrap_or("Any");
+        format!("{} → {}, {}", label(self.from), label(self.to), self.target.label(friends, groups))
+    }
+}
+
+impl RuleTarget {
+    // Friends and groups deleted after the rule was created are shown as unknown, the rule then never matches
+    pub fn label(&self, friends: &[Friend], groups: &[FriendGroup]) -> String {
+        match self {
+            RuleTarget::Anyone => "Anyone".to_string(),
+            RuleTarget::Friend(f) => friends.iter().find(|_f| _f.uuid == *f).map(|_f| _f.name.clone()).unwrap_or("Unknown friend".to_string()),
+            RuleTarget::Group(g) => groups
+                .iter()
+                .find(|_g| _g.uuid == *g)
+                .map(|_g| format!("Group {}", _g.name))
+                .unwrap_or("Unknown group".to_string()),
+        }
+    }
+}
+
+impl RuleAction {
+    /// One action of each kind, used as the starting value when the kind is changed
+    pub fn kinds() -> [RuleAction; 4] {
+        [
+            RuleAction::Sound(Sound::default()),
+            RuleAction::Toast("{name} is now {to}".to_string()),
+            RuleAction::Webhook("".to_string()),
+            RuleAction::Command("".to_string()),
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleAction::Sound(_) => "Sound",
+            RuleAction::Toast(_) => "Toast",
+            RuleAction::Webhook(_) => "Webhook",
+            RuleAction::Command(_) => "Command",
+        }
+    }
+}
+
+/// Posts the transition to the url on a separate thread, failures are ignored
+pub fn post_webhook(url: &str, transition: &Transition) {
+    let url = url.to_string();
+    let body = serde_json::json!({
+        "friend": transition.name,
+        "from": transition.from.label(),
+        "to": transition.to.label(),
+    })
+    .to_string();
+    thread::spawn(move || {
+        let agent: ureq::Agent = ureq::Agent::config_builder().timeout_global(Some(WEBHOOK_TIMEOUT)).build().into();
+        let _ = agent.post(&url).content_type("application/json").send(&body);
+    });
+}
+
+/// Runs the command through the system shell on a separate thread, failures are ignored
+pub fn run_command(command: &str, transition: &Transition) {
+    let mut cmd = shell(command);
+    cmd.env("FRIEND_NAME", transition.name)
+        .env("FROM_STATUS", transition.from.label())
+        .env("TO_STATUS", transition.to.label());
+    thread::spawn(move || {
+        let _ = cmd.spawn().and_then(|mut child| child.wait());
+    });
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]).creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            enabled: true,
+            from: None,
+            to: Some(FriendStatus::Online),
+            target: RuleTarget::Anyone,
+            action: RuleAction::kinds()[1].clone(),
+            cooldown: 0,
+            volume: 100,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(friend: Uuid, group: Option<Uuid>, from: FriendStatus, to: FriendStatus) -> Transition<'static> {
+        Transition {
+            friend,
+            group,
+            name: "Ahri#EUW",
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn matches_statuses_and_any() {
+        let friend = Uuid::new_v4();
+        let rules = [
+            Rule {
+                from: Some(FriendStatus::InGame),
+                to: Some(FriendStatus::Online),
+                ..Default::default()
+            },
+            Rule {
+                from: None,
+                to: None,
+                ..Default::default()
+            },
+            Rule {
+                to: Some(FriendStatus::Away),
+                ..Default::default()
+            },
+        ];
+        let fired = evaluate(
+            &rules,
+            &transition(friend, None, FriendStatus::InGame, FriendStatus::Online),
+            &Cooldowns::new(),
+            Instant::now(),
+        );
+        assert_eq!(fired, vec![&rules[0], &rules[1]]);
+    }
+
+    #[test]
+    fn ignores_unchanged_status_and_disabled_rules() {
+        let friend = Uuid::new_v4();
+        let rules = [
+            Rule { to: None, ..Default::default() },
+            Rule {
+                enabled: false,
+                to: Some(FriendStatus::Online),
+                ..Default::default()
+            },
+        ];
+        let now = Instant::now();
+        assert!(evaluate(&rules, &transition(friend, None, FriendStatus::Away, FriendStatus::Away), &Cooldowns::new(), now).is_empty());
+        assert_eq!(
+            evaluate(&rules, &transition(friend, None, FriendStatus::Offline, FriendStatus::Online), &Cooldowns::new(), now),
+            vec![&rules[0]]
+        );
+    }
+
+    #[test]
+    fn matches_friend_and_group_targets() {
+        let (friend, other, group) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let rules = [
+            Rule {
+                target: RuleTarget::Friend(friend),
+                ..Default::default()
+            },
+            Rule {
+                target: RuleTarget::Group(group),
+                ..Default::default()
+            },
+        ];
+        let now = Instant::now();
+        let online = |friend, group| transition(friend, group, FriendStatus::Offline, FriendStatus::Online);
+        assert_eq!(evaluate(&rules, &online(friend, None), &Cooldowns::new(), now), vec![&rules[0]]);
+        assert_eq!(evaluate(&rules, &online(other, Some(group)), &Cooldowns::new(), now), vec![&rules[1]]);
+        assert!(evaluate(&rules, &online(other, None), &Cooldowns::new(), now).is_empty());
+    }
+
+    #[test]
+    fn skips_rules_cooling_down_for_the_same_friend() {
+        let (friend, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let rules = [Rule {
+            cooldown: 60,
+            ..Default::default()
+        }];
+        let now = Instant::now();
+        let cooldowns = Cooldowns::from([((rules[0].uuid, friend), now - Duration::from_secs(30))]);
+        let online = |friend| transition(friend, None, FriendStatus::Offline, FriendStatus::Online);
+        assert!(evaluate(&rules, &online(friend), &cooldowns, now).is_empty());
+        assert_eq!(evaluate(&rules, &online(other), &cooldowns, now), vec![&rules[0]]);
+        assert_eq!(evaluate(&rules, &online(friend), &cooldowns, now + Duration::from_secs(30)), vec![&rules[0]]);
+    }
+
+    #[test]
+    fn default_transition_follows_settings() {
+        use FriendStatus::*;
+        assert!(is_default_transition(Offline, Online, false, false));
+        assert!(!is_default_transition(Away, Online, true, false));
+        assert!(!is_default_transition(Online, InGame, false, false));
+        assert!(is_default_transition(Offline, InGame, false, true));
+        assert!(!is_default_transition(Online, InGame, false, true));
+        assert!(!is_default_transition(Offline, Away, false, true));
+        assert!(is_default_transition(Mobile, Away, true, false));
+    }
+}
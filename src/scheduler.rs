@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::engine::Message;
+
+/// Handle to the reminders scheduler thread, each friend has at most one reminder pending
+/// When a reminder is due the thread sends Message::Notify with the friend uuid
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    sx: Sender<(Instant, Uuid)>,
+    // Deadline of the pending reminder of each friend, entries of the thread heap not matching it are stale
+    deadlines: Arc<Mutex<HashMap<Uuid, Instant>>>,
+}
+
+impl Scheduler {
+    /// Starts the scheduler thread, it stops once every handle is dropped
+    pub fn start(e_sx: Sender<Message>) -> Self {
+        let (sx, rx) = channel::<(Instant, Uuid)>();
+        let deadlines = Arc::new(Mutex::new(HashMap::new()));
+        let shared = deadlines.clone();
+        thread::spawn(move || {
+            let mut heap: BinaryHeap<Reverse<(Instant, Uuid)>> = BinaryHeap::new();
+            loop {
+                // Sleep until the next deadline or until a new reminder is scheduled
+                let next = heap.peek().map(|Reverse((at, _))| at.saturating_duration_since(Instant::now()));
+                let received = match next {
+                    Some(timeout) => rx.recv_timeout(timeout),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(entry) => heap.push(Reverse(entry)),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+                while let Some(&Reverse((at, friend))) = heap.peek()
+                    && at <= Instant::now()
+                {
+                    heap.pop();
+                    let mut deadlines = shared.lock().unwrap();
+                    if deadlines.get(&friend) == Some(&at) {
+                        deadlines.remove(&friend);
+                        let _ = e_sx.send(Message::Notify(friend));
+                    }
+                }
+            }
+        });
+        Self { sx, deadlines }
+    }
+
+    /// Schedules a reminder for the friend, replacing the pending one if any
+    pub fn schedule(&self, friend: Uuid, delay: Duration) {
+        let at = Instant::now() + delay;
+        self.deadlines.lock().unwrap().insert(friend, at);
+        let _ = self.sx.send((at, friend));
+    }
+
+    pub fn cancel(&self, friend: Uuid) {
+        self.deadlines.lock().unwrap().remove(&friend);
+    }
+
+    /// Time left before the pending reminder of the friend
+    pub fn remaining(&self, friend: Uuid) -> Option<Duration> {
+        self.deadlines.lock().unwrap().get(&friend).map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+// Handle not connected to any thread, replaced when the app starts
+impl Default for Scheduler {
+    fn default() -> Self {
+        let (sx, _) = channel();
+        Self {
+            sx,
+            deadlines: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notified(msg: Message) -> Option<Uuid> {
+        match msg {
+            Message::Notify(friend) => Some(friend),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn fires_reminders_in_deadline_order() {
+        let (e_sx, e_rx) = channel();
+        let scheduler = Scheduler::start(e_sx);
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        scheduler.schedule(second, Duration::from_millis(60));
+        scheduler.schedule(first, Duration::from_millis(20));
+        assert!(scheduler.remaining(second).is_some());
+        assert_eq!(e_rx.recv_timeout(Duration::from_secs(1)).ok().and_then(notified), Some(first));
+        assert_eq!(e_rx.recv_timeout(Duration::from_secs(1)).ok().and_then(notified), Some(second));
+        assert_eq!(scheduler.remaining(second), None);
+    }
+
+    #[test]
+    fn cancelled_and_rescheduled_reminders_fire_once() {
+        let (e_sx, e_rx) = channel();
+        let scheduler = Scheduler::start(e_sx);
+        let (cancelled, rescheduled) = (Uuid::new_v4(), Uuid::new_v4());
+        scheduler.schedule(cancelled, Duration::from_millis(20));
+        scheduler.schedule(rescheduled, Duration::from_millis(20));
+        scheduler.cancel(cancelled);
+        scheduler.schedule(rescheduled, Duration::from_millis(80));
+        assert_eq!(e_rx.recv_timeout(Duration::from_secs(1)).ok().and_then(notified), Some(rescheduled));
+        assert!(e_rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
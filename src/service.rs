@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Sender, channel};
+
+use crate::audio::{self, AudioState};
+use crate::config::Config;
+use crate::engine::{Engine, EngineHandle, Message};
+use crate::history::History;
+use crate::source::{self, LeagueClientSource, PresenceSource, ScriptedSource};
+use crate::websocket::{self, LinkState};
+
+/// Engine and audio thread shared by the front-ends
+pub struct Service {
+    pub engine: EngineHandle,
+    // Feeds the audio thread, e.g. to preview sounds
+    pub s_sx: Sender<Message>,
+    pub audio: Arc<AudioState>,
+}
+
+/// Starts the engine along with the client polling, websocket and audio threads
+/// In demo mode a scripted client replaces League and there is no websocket
+/// `on_change` is called every time the engine publishes a snapshot or the audio state changes
+pub fn start(config: Config, history: History, demo: bool, on_change: impl Fn() + Clone + Send + 'static) -> Service {
+    let (s_sx, s_rx) = channel::<Message>();
+    let engine = Engine::start(config, s_sx.clone(), history, on_change.clone());
+
+    let link = Arc::new(LinkState::default());
+    let source: Box<dyn PresenceSource> = match demo {
+        true => Box::new(ScriptedSource::demo()),
+        false => Box::new(LeagueClientSource::new()),
+    };
+    source::start_polling_league_client(engine.sender(), source, link.clone());
+    if !demo {
+        websocket::start_league_client_websocket(engine.sender(), link);
+    }
+    let audio = Arc::new(AudioState::default());
+    audio::start_audio_message_receiver(s_rx, audio.clone(), on_change);
+    Service { engine, s_sx, audio }
+}
@@ -0,0 +1,294 @@
+use lolclientapi_rs::blocking::LeagueClient;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::engine::Message;
+use crate::presence::ApiFriend;
+use crate::presence::{GamePhase, Presence};
+use crate::websocket::{LcuCredentials, LinkState};
+
+const POLLING_INTERVAL: Duration = Duration::from_secs(3);
+// While the websocket is pushing presence changes the full friends list is only fetched to resync
+const POLLING_RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Provides the client status and the friends presence to the polling thread
+/// The real League client is one implementation, a scripted fake allows running without it
+pub trait PresenceSource: Send {
+    /// Tries to (re)establish a connection, returns whether it succeeded
+    fn connect(&mut self) -> bool;
+    /// Whether the source is currently connected
+    fn status(&mut self) -> bool;
+    /// Retrieves the whole friends list, None if the request failed
+    fn friends(&mut self) -> Option<Vec<ApiFriend>>;
+    /// Credentials of the lcu websocket, looked up once the source is connected
+    fn credentials(&mut self) -> Option<LcuCredentials> {
+        None
+    }
+}
+
+/// Source backed by the lcu api of a running League client
+pub struct LeagueClientSource {
+    client: LeagueClient,
+}
+
+impl LeagueClientSource {
+    pub fn new() -> Self {
+        Self { client: LeagueClient::new() }
+    }
+}
+
+impl Default for LeagueClientSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PresenceSource for LeagueClientSource {
+    fn connect(&mut self) -> bool {
+        self.client.retry()
+    }
+
+    fn status(&mut self) -> bool {
+        self.client.status()
+    }
+
+    // Maps the api friends into ApiFriend
+    fn friends(&mut self) -> Option<Vec<ApiFriend>> {
+        let f = self.client.get_lol_chat_v1_friends().ok()?;
+        Some(
+            f.into_iter()
+                .map(|_f| ApiFriend {
+                    puuid: _f.puuid,
+                    riot_id: _f.game_name + "#" + &_f.game_tag,
+                    availability: _f.availability.to_lowercase(),
+                    group_name: _f.group_name,
+                    presence: Presence::from_fields(&_f.lol),
+                })
+                .collect(),
+        )
+    }
+
+    fn credentials(&mut self) -> Option<LcuCredentials> {
+        LcuCredentials::discover()
+    }
+}
+
+/// A single state of a scripted source, replayed for one poll
+#[derive(Debug, Clone, Default)]
+pub struct ScriptStep {
+    pub connected: bool,
+    pub friends: Vec<ApiFriend>,
+}
+
+impl ScriptStep {
+    /// Connected step where each friend is a (riot_id, availability) pair
+    /// The puuid is derived from the riot id, use `renamed` to keep it across a rename
+    pub fn connected(friends: &[(&str, &str)]) -> Self {
+        Self {
+            connected: true,
+            friends: friends
+                .iter()
+                .map(|(riot_id, availability)| ApiFriend {
+                    puuid: format!("scripted-{}", riot_id.to_lowercase()),
+                    riot_id: riot_id.to_string(),
+                    availability: availability.to_lowercase(),
+                    group_name: "".to_string(),
+                    presence: Presence::default(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn disconnected() -> Self {
+        Self::default()
+    }
+
+    /// Sets the game presence of the friend named `riot_id`, the game is started at the time of the poll
+    pub fn in_game(mut self, riot_id: &str, queue_type: &str, champion_id: u32) -> Self {
+        if let Some(f) = self.friends.iter_mut().find(|f| f.is_named(riot_id)) {
+            f.presence = Presence {
+                game_phase: GamePhase::InGame,
+                queue_type: Some(queue_type.to_string()),
+                champion_id: Some(champion_id),
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64),
+            };
+        }
+        self
+    }
+
+    /// Changes the riot id of the friend currently named `from`, keeping its puuid
+    pub fn renamed(mut self, from: &str, to: &str) -> Self {
+        if let Some(f) = self.friends.iter_mut().find(|f| f.is_named(from)) {
+            f.riot_id = to.to_string();
+        }
+        self
+    }
+}
+
+/// In-process fake that replays a list of steps, advancing one step on each poll
+/// The script loops once the last step is reached, friends keep the riot id they ended the first run with
+#[derive(Debug, Clone)]
+pub struct ScriptedSource {
+    steps: Vec<ScriptStep>,
+    current: usize,
+    polls: usize,
+    // Riot ids by puuid as of the end of the first run, so renames only happen once
+    riot_ids: HashMap<String, String>,
+}
+
+impl ScriptedSource {
+    pub fn new(steps: Vec<ScriptStep>) -> Self {
+        Self {
+            steps,
+            current: 0,
+            polls: 0,
+            riot_ids: HashMap::new(),
+        }
+    }
+
+    /// Script used by the demo mode, it cycles the demo friends through every status
+    pub fn demo() -> Self {
+        let [ahri, garen, lux] = DEMO_FRIENDS;
+        Self::new(vec![
+            ScriptStep::disconnected(),
+            ScriptStep::connected(&[(ahri, "offline"), (garen, "offline"), (lux, "mobile")]),
+            ScriptStep::connected(&[(ahri, "chat"), (garen, "offline"), (lux, "mobile")]),
+            ScriptStep::connected(&[(ahri, "chat"), (garen, "away"), (lux, "mobile")]),
+            ScriptStep::connected(&[(ahri, "dnd"), (garen, "chat"), (lux, "offline")]).in_game(ahri, "RANKED_SOLO_5x5", 103),
+            ScriptStep::connected(&[(ahri, "dnd"), (garen, "chat"), (lux, "chat")]).in_game(ahri, "RANKED_SOLO_5x5", 103),
+            ScriptStep::connected(&[(ahri, "chat"), (garen, "dnd"), (lux, "away")]).in_game(garen, "ARAM_UNRANKED_5x5", 86),
+            ScriptStep::connected(&[(ahri, "offline"), (garen, "dnd"), (lux, "offline")]).in_game(garen, "ARAM_UNRANKED_5x5", 86),
+            ScriptStep::connected(&[(ahri, "offline"), (garen, "chat"), (lux, "offline")]).renamed(garen, "Demacia#DEMO"),
+        ])
+    }
+
+    fn step(&self) -> Option<&ScriptStep> {
+        self.steps.get(self.current)
+    }
+}
+
+/// Riot ids of the friends replayed by the demo script
+pub const DEMO_FRIENDS: [&str; 3] = ["Ahri#DEMO", "Garen#DEMO", "Lux#DEMO"];
+
+impl PresenceSource for ScriptedSource {
+    fn connect(&mut self) -> bool {
+        self.step().is_some_and(|s| s.connected)
+    }
+
+    // Every poll starts by checking the status, so this is where the script advances
+    fn status(&mut self) -> bool {
+        if !self.steps.is_empty() {
+            self.current = self.polls % self.steps.len();
+            self.polls += 1;
+        }
+        self.step().is_some_and(|s| s.connected)
+    }
+
+    fn friends(&mut self) -> Option<Vec<ApiFriend>> {
+        let mut friends = self.step().filter(|s| s.connected)?.friends.clone();
+        let is_first_run = self.polls <= self.steps.len();
+        for f in friends.iter_mut() {
+            match self.riot_ids.get(&f.puuid) {
+                _ if is_first_run => {
+                    self.riot_ids.insert(f.puuid.clone(), f.riot_id.clone());
+                }
+                Some(riot_id) => f.riot_id = riot_id.clone(),
+                None => {}
+            }
+        }
+        Some(friends)
+    }
+}
+
+/// Thread responsible to periodically run operations on the lcu api
+/// The main goals are to retrieve the client status and available friends every num seconds,
+/// when the websocket is connected friends are only fetched on (re)connection and every resync interval
+pub fn start_polling_league_client(e_sx: Sender<Message>, mut source: Box<dyn PresenceSource>, link: Arc<LinkState>) {
+    thread::spawn(move || {
+        let mut last_sync: Option<Instant> = None;
+        let mut was_websocket_connected = false;
+        let mut was_connected = None;
+
+        loop {
+            let is_connected = source.status() || source.connect();
+            // Only changes are sent, every message makes the engine publish a new snapshot
+            if was_connected != Some(is_connected) {
+                let _ = e_sx.send(Message::ClientStatus(is_connected));
+                was_connected = Some(is_connected);
+                // The websocket thread connects with them, they change every time the client starts
+                if !is_connected {
+                    *link.credentials.lock().unwrap() = None;
+                }
+            }
+            // The lockfile can be written after the client first answers, so they are looked up until found
+            if is_connected {
+                let mut credentials = link.credentials.lock().unwrap();
+                if credentials.is_none() {
+                    *credentials = source.credentials();
+                }
+            }
+
+            // Deltas could have been missed while the websocket was reconnecting, force a resync
+            let is_websocket_connected = link.websocket_connected.load(Ordering::Relaxed);
+            if is_websocket_connected && !was_websocket_connected {
+                last_sync = None;
+            }
+            was_websocket_connected = is_websocket_connected;
+
+            let should_sync = !is_websocket_connected || last_sync.is_none_or(|t| t.elapsed() >= POLLING_RESYNC_INTERVAL);
+
+            // Retrieves friends from the source then sends them to the engine
+            if is_connected
+                && should_sync
+                && let Some(f) = source.friends()
+            {
+                let _ = e_sx.send(Message::FriendStatus(f));
+                last_sync = Some(Instant::now());
+            }
+            thread::sleep(POLLING_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_steps_are_replayed_one_per_poll() {
+        let mut source = ScriptedSource::new(vec![ScriptStep::disconnected(), ScriptStep::connected(&[("Ahri#DEMO", "chat"), ("Garen#DEMO", "offline")])]);
+        // A disconnected step refuses to connect and has no friends list
+        assert!(!source.status());
+        assert!(!source.connect());
+        assert!(source.friends().is_none());
+
+        assert!(source.status());
+        let availabilities: Vec<String> = source.friends().unwrap().into_iter().map(|f| f.availability).collect();
+        assert_eq!(availabilities, ["chat", "offline"]);
+
+        // The script loops once the last step is reached
+        assert!(!source.status());
+        assert!(source.status());
+    }
+
+    #[test]
+    fn renames_are_kept_when_the_script_loops() {
+        let mut source = ScriptedSource::new(vec![
+            ScriptStep::connected(&[("Garen#DEMO", "chat")]),
+            ScriptStep::connected(&[("Garen#DEMO", "chat")]).renamed("Garen#DEMO", "Demacia#DEMO"),
+        ]);
+        let mut riot_ids = vec![];
+        for _ in 0..4 {
+            assert!(source.status());
+            let friends = source.friends().unwrap();
+            assert_eq!(friends[0].puuid, "scripted-garen#demo");
+            riot_ids.push(friends[0].riot_id.clone());
+        }
+        assert_eq!(riot_ids, ["Garen#DEMO", "Demacia#DEMO", "Demacia#DEMO", "Demacia#DEMO"]);
+    }
+}
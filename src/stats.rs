@@ -0,0 +1,204 @@
+use chrono::{Datelike, Days, Local, NaiveDate, TimeZone, Timelike};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::history::{HistoryEntry, HistoryEvent};
+use crate::presence::FriendStatus;
+
+/// Activity of a friend computed from the status changes recorded in the history
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FriendStats {
+    pub friend: Uuid,
+    pub name: String,
+    // Seconds spent online and in game on each local day, in game time is part of the online time
+    pub days: BTreeMap<NaiveDate, DayStats>,
+    // Logins by local weekday, starting on Monday, and hour of the day
+    pub logins: [[u32; 24]; 7],
+    pub sessions: u32,
+    pub average_session: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayStats {
+    pub online_secs: u64,
+    pub in_game_secs: u64,
+}
+
+impl DayStats {
+    pub fn online_hours(&self) -> f32 {
+        self.online_secs as f32 / 3600.0
+    }
+
+    pub fn in_game_hours(&self) -> f32 {
+        self.in_game_secs as f32 / 3600.0
+    }
+}
+
+// Mobile friends are not on the desktop client, they count as offline like in the notifications
+fn is_online(status: FriendStatus) -> bool {
+    matches!(status, FriendStatus::Online | FriendStatus::Away | FriendStatus::InGame)
+}
+
+/// Stats of the friend from the history entries, oldest first
+/// The last recorded status is assumed to last until `now`, a unix timestamp in seconds
+pub fn compute(entries: &[HistoryEntry], friend: Uuid, now: i64) -> FriendStats {
+    let mut stats = FriendStats { friend, ..Default::default() };
+    let mut current: Option<(FriendStatus, i64)> = None;
+    let mut session_start: Option<i64> = None;
+    let mut session_secs = 0;
+    let transitions = entries.iter().filter(|e| e.friend == friend).filter_map(|e| match e.event {
+        HistoryEvent::Status { from, to } => Some((e, from, to)),
+        _ => None,
+    });
+    for (entry, from, to) in transitions {
+        stats.name = entry.name.clone();
+        if let Some((status, since)) = current {
+            // Not starting from the last recorded status means entries are missing, e.g. the app was killed and restarted
+            // How long the status lasted is unknown, so it and the session end where it was recorded
+            if status != from {
+                if let Some(start) = session_start.take() {
+                    stats.sessions += 1;
+                    session_secs += (since - start).max(0) as u64;
+                }
+            } else {
+                stats.add_interval(status, since, entry.timestamp);
+            }
+        }
+        if is_online(to) && session_start.is_none() {
+            session_start = Some(entry.timestamp);
+            if let Some(time) = Local.timestamp_opt(entry.timestamp, 0).single() {
+                stats.logins[time.weekday().num_days_from_monday() as usize][time.hour() as usize] += 1;
+            }
+        } else if !is_online(to)
+            && let Some(start) = session_start.take()
+        {
+            stats.sessions += 1;
+            session_secs += (entry.timestamp - start).max(0) as u64;
+        }
+        current = Some((to, entry.timestamp));
+    }
+    if let Some((status, since)) = current {
+        stats.add_interval(status, since, now);
+    }
+    if let Some(start) = session_start {
+        stats.sessions += 1;
+        session_secs += (now - start).max(0) as u64;
+    }
+    if stats.sessions > 0 {
+        stats.average_session = Duration::from_secs(session_secs / stats.sessions as u64);
+    }
+    stats
+}
+
+impl FriendStats {
+    // Adds the time spent in the status, split on local midnights
+    fn add_interval(&mut self, status: FriendStatus, mut start: i64, end: i64) {
+        if !is_online(status) {
+            return;
+        }
+        while start < end {
+            let Some(time) = Local.timestamp_opt(start, 0).single() else { return };
+            let date = time.date_naive();
+            let midnight = date
+                .checked_add_days(Days::new(1))
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .and_then(|d| Local.from_local_datetime(&d).earliest())
+                .map_or(end, |d| d.timestamp());
+            let until = end.min(midnight.max(start + 1));
+            let day = self.days.entry(date).or_default();
+            day.online_secs += (until - start) as u64;
+            if status == FriendStatus::InGame {
+                day.in_game_secs += (until - start) as u64;
+            }
+            start = until;
+        }
+    }
+}
+
+/// One line per friend and day, hours are rounded to two decimals
+pub fn to_csv(stats: &[FriendStats]) -> String {
+    let mut csv = "friend,date,online_hours,in_game_hours\n".to_string();
+    for s in stats {
+        let name = format!("\"{}\"", s.name.replace('"', "\"\""));
+        for (date, day) in &s.days {
+            csv += &format!("{name},{date},{:.2},{:.2}\n", day.online_hours(), day.in_game_hours());
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(friend: Uuid, date: NaiveDate, hour: u32, from: FriendStatus, to: FriendStatus) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(friend, "Ahri#EUW", HistoryEvent::Status { from, to });
+        entry.timestamp = Local.from_local_datetime(&date.and_hms_opt(hour, 0, 0).unwrap()).earliest().unwrap().timestamp();
+        entry
+    }
+
+    #[test]
+    fn sessions_are_split_on_midnight_and_counted_once() {
+        use FriendStatus::*;
+        let friend = Uuid::new_v4();
+        // A Monday
+        let day = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let next = day.succ_opt().unwrap();
+        let entries = vec![
+            at(friend, day, 20, Offline, Online),
+            at(friend, day, 21, Online, InGame),
+            at(friend, day, 23, InGame, Away),
+            at(friend, next, 1, Away, Offline),
+            at(Uuid::new_v4(), day, 22, Offline, Online),
+        ];
+        let end = entries[3].timestamp;
+        let stats = compute(&entries, friend, end + 3600);
+
+        assert_eq!(
+            stats.days[&day],
+            DayStats {
+                online_secs: 4 * 3600,
+                in_game_secs: 2 * 3600
+            }
+        );
+        assert_eq!(
+            stats.days[&next],
+            DayStats {
+                online_secs: 3600,
+                in_game_secs: 0
+            }
+        );
+        assert_eq!(stats.logins[0][20], 1);
+        assert_eq!(stats.logins.iter().flatten().sum::<u32>(), 1);
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(stats.average_session, Duration::from_secs(5 * 3600));
+        assert_eq!(to_csv(&[stats]).lines().nth(1), Some("\"Ahri#EUW\",2025-03-03,4.00,2.00"));
+    }
+
+    #[test]
+    fn missing_offline_entries_do_not_count_the_gap_as_online() {
+        use FriendStatus::*;
+        let friend = Uuid::new_v4();
+        let day = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        // The app was killed while the friend was in game and restarted three hours later
+        let entries = vec![
+            at(friend, day, 10, Offline, Online),
+            at(friend, day, 11, Online, InGame),
+            at(friend, day, 14, Offline, Online),
+            at(friend, day, 15, Online, Offline),
+        ];
+        let stats = compute(&entries, friend, entries[3].timestamp + 3600);
+
+        assert_eq!(
+            stats.days[&day],
+            DayStats {
+                online_secs: 2 * 3600,
+                in_game_secs: 0
+            }
+        );
+        assert_eq!(stats.sessions, 2);
+        assert_eq!(stats.logins[0][10] + stats.logins[0][14], 2);
+        assert_eq!(stats.average_session, Duration::from_secs(3600));
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(not(target_os = "linux"))]
+pub use native::Tray;
+#[cfg(target_os = "linux")]
+pub use unsupported::Tray;
+
+#[cfg(not(target_os = "linux"))]
+mod native {
+    use eframe::egui::{self, ViewportCommand};
+    use std::cell::RefCell;
+    use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder, TrayIconEvent};
+    use uuid::Uuid;
+
+    use crate::headless::ConfigStore;
+    use lolfriendsnotifier::config::Config;
+    use lolfriendsnotifier::engine::{EngineHandle, Message};
+
+    const MENU_OPEN: &str = "open";
+    const MENU_MUTE: &str = "mute";
+    const MENU_QUIT: &str = "quit";
+
+    /// Tray icon showing the client status, its menu toggles the tracked friends and reopens the window
+    pub struct Tray;
+
+    // State the icon and menu were last built from, they are only rebuilt when it changes
+    struct TrayState {
+        icon: TrayIcon,
+        client_status: Option<bool>,
+        friends: Vec<(Uuid, String, bool)>,
+        muted: bool,
+    }
+
+    thread_local! {
+        // The icon belongs to the thread running the event loop, the tray event handlers are called on it as well
+        static STATE: RefCell<Option<TrayState>> = const { RefCell::new(None) };
+    }
+
+    impl Tray {
+        /// In demo mode the configuration is not saved when quitting
+        pub fn new(ctx: &egui::Context, engine: EngineHandle, demo: bool) -> Option<Self> {
+            let icon = TrayIconBuilder::new().with_tooltip("Friends Notifier").with_icon(status_icon(false)?).build().ok()?;
+            STATE.set(Some(TrayState {
+                icon,
+                client_status: None,
+                friends: vec![],
+                muted: false,
+            }));
+            let ctx = ctx.clone();
+            let menu_engine = engine.clone();
+            // The window might be hidden and not drawing frames, every entry is handled right from the handler
+            MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+                match event.id.0.as_str() {
+                    MENU_OPEN => {
+                        ctx.send_viewport_cmd(ViewportCommand::Visible(true));
+                        ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+                        ctx.send_viewport_cmd(ViewportCommand::Focus);
+                    }
+                    MENU_QUIT => quit(&menu_engine, demo),
+                    id => toggle(&menu_engine, id),
+                }
+                ctx.request_repaint();
+            }));
+            // For the same reason the icon and menu are refreshed from the engine when the icon is hovered or clicked, before the menu opens
+            TrayIconEvent::set_event_handler(Some(move |_: TrayIconEvent| {
+                let snapshot = engine.snapshot();
+                update(&snapshot.config, snapshot.client_status);
+            }));
+            Some(Self)
+        }
+
+        // Called on every frame while the window is drawn
+        pub fn update(&self, config: &Config, client_status: bool) {
+            update(config, client_status);
+        }
+    }
+
+    fn update(config: &Config, client_status: bool) {
+        STATE.with_borrow_mut(|state| {
+            if let Some(state) = state {
+                state.update(config, client_status);
+            }
+        });
+    }
+
+    impl TrayState {
+        fn update(&mut self, config: &Config, client_status: bool) {
+            if self.client_status != Some(client_status) {
+                self.client_status = Some(client_status);
+                let _ = self.icon.set_icon(status_icon(client_status));
+                let tooltip = match client_status {
+                    true => "Friends Notifier - Client connected",
+                    false => "Friends Notifier - Client disconnected",
+                };
+                let _ = self.icon.set_tooltip(Some(tooltip));
+            }
+            // A friend is only listed once it has a name
+            let friends: Vec<(Uuid, String, bool)> = config.friends.iter().filter(|f| !f.name.is_empty()).map(|f| (f.uuid, f.name.clone(), f.enabled)).collect();
+            if self.friends != friends || self.muted != config.muted {
+                self.friends = friends;
+                self.muted = config.muted;
+                self.icon.set_menu(Some(Box::new(self.menu())));
+            }
+        }
+
+        fn menu(&self) -> Menu {
+            let menu = Menu::new();
+            let _ = menu.append(&MenuItem::with_id(MENU_OPEN, "Open", true, None));
+            let _ = menu.append(&PredefinedMenuItem::separator());
+            for (uuid, name, enabled) in &self.friends {
+                let _ = menu.append(&CheckMenuItem::with_id(uuid.to_string(), name, true, *enabled, None));
+            }
+            if !self.friends.is_empty() {
+                let _ = menu.append(&PredefinedMenuItem::separator());
+            }
+            let _ = menu.append(&CheckMenuItem::with_id(MENU_MUTE, "Mute sounds", true, self.muted, None));
+            let _ = menu.append(&MenuItem::with_id(MENU_QUIT, "Quit", true, None));
+            menu
+        }
+    }
+
+    // Only the toggled value is sent, the gui takes it from the next snapshot
+    fn toggle(engine: &EngineHandle, id: &str) {
+        let config = engine.snapshot().config;
+        if id == MENU_MUTE {
+            engine.send(Message::SetMuted(!config.muted));
+        } else if let Some(f) = Uuid::parse_str(id).ok().and_then(|uuid| config.friends.into_iter().find(|f| f.uuid == uuid)) {
+            engine.send(Message::SetFriendEnabled(f.uuid, !f.enabled));
+        }
+    }
+
+    // Closing the viewport needs a frame, which a hidden window might never draw, so the configuration is saved here
+    fn quit(engine: &EngineHandle, demo: bool) {
+        if !demo {
+            ConfigStore::new().save(&engine.snapshot().config);
+        }
+        engine.shutdown();
+        std::process::exit(0);
+    }
+
+    // App icon in its colours while the client is connected, greyed out otherwise
+    fn status_icon(client_status: bool) -> Option<Icon> {
+        let mut icon = eframe::icon_data::from_png_bytes(crate::ASSET_ICON).ok()?;
+        if !client_status {
+            for pixel in icon.rgba.chunks_exact_mut(4) {
+                let grey = ((pixel[0] as u16 * 30 + pixel[1] as u16 * 59 + pixel[2] as u16 * 11) / 100) as u8;
+                pixel[..3].fill(grey);
+            }
+        }
+        Icon::from_rgba(icon.rgba, icon.width, icon.height).ok()
+    }
+}
+
+// Tray icons need a gtk event loop on linux, closing the window quits the app there
+#[cfg(target_os = "linux")]
+mod unsupported {
+    use eframe::egui;
+
+    use lolfriendsnotifier::config::Config;
+    use lolfriendsnotifier::engine::EngineHandle;
+
+    pub struct Tray;
+
+    impl Tray {
+        pub fn new(_ctx: &egui::Context, _engine: EngineHandle, _demo: bool) -> Option<Self> {
+            None
+        }
+
+        pub fn update(&self, _config: &Config, _client_status: bool) {}
+    }
+}
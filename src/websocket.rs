@@ -0,0 +1,220 @@
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use native_tls::TlsConnector;
+use serde_json::Value;
+use std::net::TcpStream;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{
+    Connector, Message as WsMessage,
+    client::IntoClientRequest,
+    client_tls_with_config,
+    http::{HeaderValue, header::AUTHORIZATION},
+};
+
+use crate::engine::Message;
+use crate::presence::{ApiFriend, Presence};
+
+// WAMP opcodes used by the LCU websocket, the client replies to a subscribe with events for that topic
+const WAMP_SUBSCRIBE: u8 = 5;
+const WAMP_EVENT: u64 = 8;
+const FRIENDS_TOPIC: &str = "OnJsonApiEvent_lol-chat_v1_friends";
+
+// Lockfile locations of default installs, used before falling back to reading the process command line
+const LOCKFILE_PATHS: [&str; 2] = ["C:\\Riot Games\\League of Legends\\lockfile", "/Applications/League of Legends.app/Contents/LoL/lockfile"];
+
+/// Connection state shared between the polling and websocket threads
+#[derive(Debug, Default)]
+pub struct LinkState {
+    // Resolved by the polling thread when the client connects, None while it is closed
+    pub credentials: Mutex<Option<LcuCredentials>>,
+    pub websocket_connected: AtomicBool,
+}
+
+/// Port and auth token required to open a connection to the lcu
+#[derive(Debug, Clone, PartialEq)]
+pub struct LcuCredentials {
+    pub port: u16,
+    pub password: String,
+}
+
+impl LcuCredentials {
+    /// Looks for the credentials of the running client, this might spawn a process so it is only done on connection
+    pub fn discover() -> Option<Self> {
+        Self::from_lockfile().or_else(Self::from_process)
+    }
+
+    // Lockfile format is 'LeagueClient:pid:port:password:protocol'
+    fn from_lockfile() -> Option<Self> {
+        LOCKFILE_PATHS.iter().find_map(|path| {
+            let content = std::fs::read_to_string(path).ok()?;
+            let mut parts = content.trim().split(':').skip(2);
+            Some(Self {
+                port: parts.next()?.parse().ok()?,
+                password: parts.next()?.to_string(),
+            })
+        })
+    }
+
+    // The ux process is always started with the port and token as arguments, this covers custom install locations
+    fn from_process() -> Option<Self> {
+        let output = process_command_lines()?;
+        let arg = |name: &str| output.split(['"', ' ']).find_map(|a| a.strip_prefix(name)).map(|v| v.to_string());
+        Some(Self {
+            port: arg("--app-port=")?.parse().ok()?,
+            password: arg("--remoting-auth-token=")?,
+        })
+    }
+}
+
+#[cfg(windows)]
+fn process_command_lines() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process -Filter \"name='LeagueClientUx.exe'\" | Select-Object -ExpandProperty CommandLine",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(not(windows))]
+fn process_command_lines() -> Option<String> {
+    let output = Command::new("ps").args(["-A", "-o", "args="]).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().find(|l| l.contains("LeagueClientUx")).map(|l| l.to_string())
+}
+
+/// Thread responsible to subscribe to the lcu websocket and push friends presence changes as they happen
+/// The polling thread keeps running as a fallback and only resyncs the full list while this one is connected
+pub fn start_league_client_websocket(e_sx: Sender<Message>, link: Arc<LinkState>) {
+    thread::spawn(move || {
+        loop {
+            let credentials = link.credentials.lock().unwrap().clone();
+            if let Some(credentials) = credentials {
+                let _ = run_websocket(&credentials, &e_sx, &link);
+                link.websocket_connected.store(false, Ordering::Relaxed);
+            }
+            thread::sleep(Duration::from_secs(5));
+        }
+    });
+}
+
+// Blocks until the websocket gets closed or errors
+fn run_websocket(credentials: &LcuCredentials, e_sx: &Sender<Message>, link: &LinkState) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request = format!("wss://127.0.0.1:{}/", credentials.port).into_client_request()?;
+    let auth = BASE64.encode(format!("riot:{}", credentials.password));
+    request.headers_mut().insert(AUTHORIZATION, HeaderValue::from_str(&format!("Basic {auth}"))?);
+
+    // The lcu uses a self signed certificate
+    let connector = TlsConnector::builder().danger_accept_invalid_certs(true).build()?;
+    let stream = TcpStream::connect(("127.0.0.1", credentials.port))?;
+    let (mut socket, _) = client_tls_with_config(request, stream, None, Some(Connector::NativeTls(connector))).map_err(|e| e.to_string())?;
+
+    socket.send(WsMessage::text(format!("[{WAMP_SUBSCRIBE}, \"{FRIENDS_TOPIC}\"]")))?;
+    link.websocket_connected.store(true, Ordering::Relaxed);
+
+    loop {
+        match socket.read()? {
+            WsMessage::Text(text) => {
+                if let Some(msg) = parse_friends_event(text.as_str()) {
+                    let _ = e_sx.send(msg);
+                }
+            }
+            WsMessage::Close(_) => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+// Events have the shape [8, topic, {"data": ..., "eventType": "Create" | "Update" | "Delete", "uri": ...}]
+fn parse_friends_event(text: &str) -> Option<Message> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    if value.get(0)?.as_u64()? != WAMP_EVENT || value.get(1)?.as_str()? != FRIENDS_TOPIC {
+        return None;
+    }
+    let event = value.get(2)?;
+    // Deleted friends carry no data, the uri ends with their id, 'puuid@server'
+    if event.get("eventType")?.as_str()? == "Delete" {
+        let id = event.get("uri")?.as_str()?.rsplit('/').next()?;
+        let puuid = id.split('@').next().filter(|p| !p.is_empty())?;
+        return Some(Message::FriendRemoved(puuid.to_string()));
+    }
+    match event.get("data")? {
+        Value::Array(friends) => Some(Message::FriendStatus(friends.iter().filter_map(api_friend_from_json).collect())),
+        data => api_friend_from_json(data).map(Message::FriendPresence),
+    }
+}
+
+fn api_friend_from_json(value: &Value) -> Option<ApiFriend> {
+    let game_name = value.get("gameName")?.as_str()?;
+    let game_tag = value.get("gameTag")?.as_str()?;
+    // Presence fields are all strings, anything else is ignored
+    let lol = value
+        .get("lol")
+        .and_then(|l| l.as_object())
+        .map(|l| l.iter().filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string()))).collect())
+        .unwrap_or_default();
+    Some(ApiFriend {
+        puuid: value.get("puuid")?.as_str()?.to_string(),
+        riot_id: format!("{game_name}#{game_tag}"),
+        availability: value.get("availability")?.as_str()?.to_lowercase(),
+        group_name: value.get("groupName").and_then(|g| g.as_str()).unwrap_or_default().to_string(),
+        presence: Presence::from_fields(&lol),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presence::GamePhase;
+
+    const FRIEND: &str = r#"{"puuid": "3b1e5c7a-puuid", "gameName": "Ahri", "gameTag": "EUW", "availability": "dnd", "groupName": "Team",
+        "lol": {"gameStatus": "inGame", "gameQueueType": "RANKED_SOLO_5x5", "championId": "103", "level": 30}}"#;
+
+    fn event(event_type: &str, data: &str, uri: &str) -> String {
+        format!(r#"[8, "{FRIENDS_TOPIC}", {{"data": {data}, "eventType": "{event_type}", "uri": "{uri}"}}]"#)
+    }
+
+    #[test]
+    fn created_and_updated_friends_are_pushed() {
+        for event_type in ["Create", "Update"] {
+            let Some(Message::FriendPresence(friend)) = parse_friends_event(&event(event_type, FRIEND, "/lol-chat/v1/friends/3b1e5c7a-puuid@eu1.pvp.net")) else {
+                panic!("{event_type} is not a presence change");
+            };
+            assert_eq!(friend.puuid, "3b1e5c7a-puuid");
+            assert_eq!(friend.riot_id, "Ahri#EUW");
+            assert_eq!(friend.availability, "dnd");
+            assert_eq!(friend.group_name, "Team");
+            assert_eq!(friend.presence.game_phase, GamePhase::InGame);
+            assert_eq!(friend.presence.champion_id, Some(103));
+        }
+        // The whole list is sent when it is replaced
+        let Some(Message::FriendStatus(friends)) = parse_friends_event(&event("Update", &format!("[{FRIEND}, {{}}]"), "/lol-chat/v1/friends")) else {
+            panic!("list update is not a friends list");
+        };
+        assert_eq!(friends.len(), 1);
+    }
+
+    #[test]
+    fn deleted_friends_are_removed() {
+        let msg = parse_friends_event(&event("Delete", "null", "/lol-chat/v1/friends/3b1e5c7a-puuid@eu1.pvp.net"));
+        assert!(matches!(msg, Some(Message::FriendRemoved(puuid)) if puuid == "3b1e5c7a-puuid"));
+    }
+
+    #[test]
+    fn other_events_are_ignored() {
+        assert!(parse_friends_event(&format!(r#"[8, "OnJsonApiEvent_lol-gameflow_v1_session", {{"data": {FRIEND}, "eventType": "Update"}}]"#)).is_none());
+        assert!(parse_friends_event(r#"[0, "session", 1, "lcu"]"#).is_none());
+        assert!(parse_friends_event("not json").is_none());
+    }
+}
@@ -0,0 +1,99 @@
+// Drives the engine with a scripted client the same way the polling thread does, without its polling interval
+
+use std::sync::mpsc::{Receiver, channel};
+use std::time::{Duration, Instant};
+
+use lolfriendsnotifier::config::{Config, Friend};
+use lolfriendsnotifier::engine::{Engine, EngineHandle, Message, Snapshot};
+use lolfriendsnotifier::history::History;
+use lolfriendsnotifier::presence::FriendStatus;
+use lolfriendsnotifier::source::{PresenceSource, ScriptStep, ScriptedSource};
+
+// One iteration of source::start_polling_league_client
+fn poll(source: &mut ScriptedSource, engine: &EngineHandle) {
+    let is_connected = source.status() || source.connect();
+    engine.send(Message::ClientStatus(is_connected));
+    if is_connected && let Some(friends) = source.friends() {
+        engine.send(Message::FriendStatus(friends));
+    }
+}
+
+fn wait_for(engine: &EngineHandle, condition: impl Fn(&Snapshot) -> bool) -> Snapshot {
+    let start = Instant::now();
+    loop {
+        if let Some(snapshot) = engine.snapshot_since(0)
+            && (condition(&snapshot) || start.elapsed() > Duration::from_secs(5))
+        {
+            return snapshot;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+// Sounds played since the last call, the notifications of the engine
+// Notifying takes one more message than changing the status, so anything sent meanwhile is waited for
+fn played(s_rx: &Receiver<Message>) -> Vec<String> {
+    std::thread::sleep(Duration::from_millis(200));
+    s_rx.try_iter()
+        .filter_map(|msg| match msg {
+            Message::PlaySound(path, ..) => Some(path),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn scripted_presence_is_debounced_and_notified() {
+    let ahri = Friend {
+        name: "Ahri#DEMO".to_string(),
+        enabled: true,
+        ..Default::default()
+    };
+    let config = Config {
+        friends: vec![ahri.clone()],
+        status_dwell_secs: 1,
+        ..Default::default()
+    };
+    let mut source = ScriptedSource::new(vec![
+        ScriptStep::connected(&[("Ahri#DEMO", "offline")]),
+        ScriptStep::connected(&[("Ahri#DEMO", "chat")]),
+        ScriptStep::connected(&[("Ahri#DEMO", "offline")]),
+        ScriptStep::connected(&[("Ahri#DEMO", "chat")]),
+        ScriptStep::connected(&[("Ahri#DEMO", "dnd")]).in_game("Ahri#DEMO", "ARAM_UNRANKED_5x5", 103),
+        ScriptStep::disconnected(),
+    ]);
+    let (s_sx, s_rx) = channel();
+    let engine = Engine::start(config, s_sx, History::default(), || {});
+    let status = |s: &Snapshot| s.config.friends[0].status;
+
+    // Coming online for less than the dwell time is not accepted
+    for _ in 0..3 {
+        poll(&mut source, &engine);
+    }
+    let snapshot = wait_for(&engine, |s| s.client_status);
+    std::thread::sleep(Duration::from_millis(1200));
+    assert_eq!(status(&snapshot), FriendStatus::Offline);
+    assert_eq!(status(&wait_for(&engine, |_| true)), FriendStatus::Offline);
+    assert!(played(&s_rx).is_empty());
+
+    // Once it lasted the dwell time the friend is notified with its sound
+    let online_since = Instant::now();
+    poll(&mut source, &engine);
+    let snapshot = wait_for(&engine, |s| status(s) == FriendStatus::Online);
+    assert_eq!(status(&snapshot), FriendStatus::Online);
+    assert!(online_since.elapsed() >= Duration::from_secs(1));
+    assert_eq!(played(&s_rx), vec![ahri.sound.path.clone()]);
+
+    // Starting a game right after is accepted but not notified during the cooldown
+    poll(&mut source, &engine);
+    let snapshot = wait_for(&engine, |s| status(s) == FriendStatus::InGame);
+    assert_eq!(status(&snapshot), FriendStatus::InGame);
+    assert!(snapshot.config.friends[0].presence.champion_id.is_some());
+    assert!(played(&s_rx).is_empty());
+
+    // Closing the client puts every friend offline right away
+    poll(&mut source, &engine);
+    let snapshot = wait_for(&engine, |s| !s.client_status);
+    assert_eq!(status(&snapshot), FriendStatus::Offline);
+    assert!(played(&s_rx).is_empty());
+}
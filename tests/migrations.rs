@@ -0,0 +1,128 @@
+// Persisted configurations of every released layout, they must keep loading without losing user data
+// Add a fixture here whenever migrations::SCHEMA_VERSION is bumped
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use lolfriendsnotifier::config::{Config, Sound};
+use lolfriendsnotifier::migrations::{self, SCHEMA_VERSION};
+use lolfriendsnotifier::presence::FriendStatus;
+use lolfriendsnotifier::rules::{RuleAction, RuleTarget};
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)).unwrap()
+}
+
+fn backup_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("friends-notifier-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn release_1_0_5_state_is_migrated() {
+    let blob = fixture("1.0.5.ron");
+    let dir = backup_dir();
+    assert_eq!(migrations::schema_version(&blob), Some(0));
+
+    let config = migrations::load(&blob, Some(&dir));
+    assert_eq!(config.schema_version, SCHEMA_VERSION);
+    assert_eq!(config.friends.len(), 2);
+    let ahri = &config.friends[0];
+    assert_eq!(ahri.uuid, Uuid::parse_str("6f1c2b8e-3c1f-4b8a-9a57-3f0d6f2f6c11").unwrap());
+    assert_eq!(ahri.name, "Ahri#EUW");
+    assert_eq!(ahri.sound.path, "assets/notification-3.mp3");
+    assert!(ahri.is_repeat);
+    assert_eq!(ahri.notify_timer, 30);
+    assert_eq!(ahri.volume, 100);
+    assert!(config.native_notification);
+    assert_eq!(config.volume, 60);
+    assert!(!config.dark_mode);
+    // Fields added since then get their defaults
+    assert_eq!(config.status_dwell_secs, Config::default().status_dwell_secs);
+    assert!(config.rules.is_empty());
+
+    // The blob is kept as it was before migrating
+    assert_eq!(std::fs::read_to_string(dir.join("app-schema-0.ron.bak")).unwrap(), blob);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn release_1_0_5_app_file_is_migrated() {
+    // The whole file eframe wrote, the app state is one of its entries
+    let file: HashMap<String, String> = ron::from_str(&fixture("1.0.5-app.ron")).unwrap();
+    let blob = &file["app"];
+    let dir = backup_dir();
+
+    let config = migrations::load(blob, Some(&dir));
+    let names: Vec<_> = config.friends.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["Garen#DEMA", "Lux#EUW"]);
+    let lux = &config.friends[1];
+    assert_eq!(lux.uuid, Uuid::parse_str("4b8e1f6a-9c2d-4a7e-b3f5-c8d9e0a1b2c3").unwrap());
+    assert_eq!(
+        lux.sound,
+        Sound {
+            label: "Sound 4".to_string(),
+            path: "assets/notification-4.mp3".to_string(),
+        }
+    );
+    assert!(lux.is_repeat);
+    assert_eq!(lux.notify_timer, 120);
+    assert_eq!(config.friends[0].notify_timer, 5);
+    assert!(!config.native_notification);
+    assert_eq!(config.volume, 100);
+    assert!(config.notify_away_status);
+    assert!(!config.notify_in_game_status);
+    assert!(config.dark_mode);
+    assert!(config.custom_sounds.is_empty());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn release_state_without_some_settings_gets_their_release_defaults() {
+    let blob = r#"(friends:[(uuid:"6f1c2b8e-3c1f-4b8a-9a57-3f0d6f2f6c11",timer_id:"0b7e4a1d-52a4-4c1e-8f3b-9d8c2e6a7f20",name:"Ahri#EUW",sound:(label:"Sound 1",path:"assets/notification-1.mp3"),is_repeat:false,notify_timer:5)],volume:40)"#;
+    let config = migrations::migrate(blob).unwrap();
+    assert_eq!(config.friends[0].name, "Ahri#EUW");
+    assert_eq!(config.volume, 40);
+    assert!(config.notify_in_game_status);
+    assert!(config.dark_mode);
+}
+
+#[test]
+fn schema_1_state_loads_as_is() {
+    let blob = fixture("schema-1.ron");
+    let dir = backup_dir();
+
+    let config = migrations::load(&blob, Some(&dir));
+    let horn = Sound {
+        label: "Horn".to_string(),
+        path: "/sounds/horn.ogg".to_string(),
+    };
+    let group = Uuid::parse_str("9e2d4f6a-1b3c-4d5e-8f70-a1b2c3d4e5f6").unwrap();
+    assert_eq!(config.friends[0].puuid.as_deref(), Some("3b1e5c7a-puuid"));
+    assert_eq!(config.friends[0].group, Some(group));
+    assert_eq!(config.friends[0].sound, horn);
+    assert_eq!(config.friends[0].game_end_volume, 50);
+    assert_eq!(config.groups[0].name, "Team");
+    assert_eq!(config.rules[0].from, Some(FriendStatus::Offline));
+    assert_eq!(config.rules[0].target, RuleTarget::Group(group));
+    assert_eq!(config.rules[0].action, RuleAction::Sound(horn.clone()));
+    assert_eq!(config.custom_sounds, vec![horn]);
+    assert_eq!(config.output_device.as_deref(), Some("Speakers"));
+    assert_eq!(config.history_retention_days, 7);
+
+    // Nothing to migrate, nothing to back up
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn unreadable_state_is_kept_and_replaced_with_the_default() {
+    let dir = backup_dir();
+    let config = migrations::load("(friends: [oops", Some(&dir));
+    assert_eq!(config.schema_version, SCHEMA_VERSION);
+    assert!(config.friends.iter().all(|f| f.name.is_empty()));
+    assert!(dir.join("app-unreadable.ron.bak").exists());
+    let _ = std::fs::remove_dir_all(dir);
+}